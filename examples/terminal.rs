@@ -0,0 +1,44 @@
+//! Drives an emulated 20x4 display and shows it in the terminal.
+//!
+//! ```text
+//! cargo run --example terminal --features std,emulator
+//! ```
+
+use core::fmt::Write;
+use std::cell::RefCell;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use serlcd::emulator::{Emulator, EmulatorSpi, NoDelay, TerminalRenderer};
+use serlcd::{Geometry, SerLCD};
+
+fn main() -> io::Result<()> {
+    let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+    let mut lcd = SerLCD::new_spi_device(EmulatorSpi::new(&emulator), NoDelay);
+    let mut renderer = TerminalRenderer::new();
+    let mut stdout = io::stdout();
+
+    lcd.setup().unwrap();
+    lcd.set_backlight(40, 120, 255).unwrap();
+    lcd.create_char(0, [0x00, 0x0a, 0x1f, 0x1f, 0x0e, 0x04, 0x00, 0x00])
+        .unwrap();
+
+    lcd.write_str("SerLCD emulator").unwrap();
+    lcd.write_char(0).unwrap();
+
+    for tick in 0..20u32 {
+        lcd.set_cursor(0, 2).unwrap();
+        write!(lcd, "tick {:>3}", tick).unwrap();
+        lcd.set_cursor(9, 2).unwrap();
+
+        if tick == 4 {
+            lcd.blink().unwrap();
+        }
+
+        renderer.draw(&emulator.borrow(), &mut stdout)?;
+        thread::sleep(Duration::from_millis(400));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,611 @@
+//! Async driver for executors such as embassy.
+//!
+//! [`AsyncSerLCD`] mirrors the blocking [`SerLCD`](crate::SerLCD) API, but
+//! awaits the settle delays instead of spinning in them.
+
+use core::convert::Infallible;
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+use embedded_hal_async::spi::SpiDevice;
+
+use crate::interface::eh1::{I2cInterface, SpiInterface};
+use crate::interface::AsyncInterface;
+use crate::*;
+
+pub struct AsyncSerLCD<IFACE, DS> {
+    interface: IFACE,
+    delay_source: DS,
+    state: State,
+    timing: Timing,
+}
+
+impl<SPI, DS> AsyncSerLCD<SpiInterface<SPI>, DS>
+where
+    SPI: SpiDevice,
+    DS: DelayNs,
+{
+    /// Creates a driver over an SPI device that manages its own chip select.
+    pub fn new_spi_device(spi: SPI, delay_source: DS) -> Self {
+        Self::with_interface(SpiInterface::new(spi), delay_source)
+    }
+}
+
+impl<I2C, DS> AsyncSerLCD<I2cInterface<I2C>, DS>
+where
+    I2C: I2c,
+    DS: DelayNs,
+{
+    /// Creates a driver talking to the display at the default address (0x72).
+    pub fn new_i2c_device(i2c: I2C, delay_source: DS) -> Self {
+        Self::new_i2c_device_with_address(i2c, DISPLAY_ADDRESS, delay_source)
+    }
+
+    /// Creates a driver talking to the display at the given 7-bit address.
+    pub fn new_i2c_device_with_address(i2c: I2C, address: u8, delay_source: DS) -> Self {
+        Self::with_interface(I2cInterface::new(i2c, address), delay_source)
+    }
+
+    /// Moves the display to a new 7-bit I2C address.
+    ///
+    /// The command is sent to the current address, after which the driver
+    /// targets `address`. The display keeps the new address across resets.
+    pub async fn change_address(
+        &mut self,
+        address: u8,
+    ) -> Result<(), Error<I2C::Error, Infallible>> {
+        self.send_address_command(address).await?;
+        self.interface.set_address(address);
+        Ok(())
+    }
+}
+
+impl<IFACE, DS> AsyncSerLCD<IFACE, DS>
+where
+    IFACE: AsyncInterface,
+    DS: DelayNs,
+{
+    /// Creates a driver over any [`AsyncInterface`] implementation.
+    pub fn with_interface(interface: IFACE, delay_source: DS) -> Self {
+        Self {
+            interface,
+            delay_source,
+            state: State::new(),
+            timing: Timing::default(),
+        }
+    }
+
+    /// Sets the panel layout, which is pushed to the display by `setup`.
+    pub fn with_geometry(mut self, geometry: Geometry) -> Self {
+        self.state.geometry = geometry;
+        self
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.state.geometry
+    }
+
+    /// Sets the delays waited out around bus traffic.
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    /// Releases the underlying bus and delay source.
+    pub fn release(self) -> (IFACE, DS) {
+        (self.interface, self.delay_source)
+    }
+
+    /// Pushes the full cached state to the display and clears it.
+    ///
+    /// This covers display, cursor and blink flags, entry mode, geometry and,
+    /// once set, backlight and contrast.
+    pub async fn setup(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let frame = self.state.init_frame();
+
+        self.begin_transmission().await?;
+        self.transmit(frame.as_slice()).await?;
+        self.end_transmission().await?;
+
+        self.delay_source
+            .delay_us(self.timing.special_command_us)
+            .await;
+
+        Ok(())
+    }
+
+    /// Recovers a display that lost power, e.g. after a brown-out.
+    ///
+    /// Waits for the display to finish booting, then runs [`setup`](Self::setup)
+    /// again so the hardware matches the state the driver believes in.
+    pub async fn reinit(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.delay_source.delay_us(self.timing.boot_us).await;
+        self.setup().await
+    }
+
+    pub async fn command(
+        &mut self,
+        command: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::Setting(command)).await
+    }
+
+    pub async fn special_command(
+        &mut self,
+        command: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.special(&Command::Special(command)).await
+    }
+
+    /// Sends `command` `count` times, batched into as few bus transfers as
+    /// the display's receive buffer allows.
+    pub async fn special_command_count(
+        &mut self,
+        command: u8,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Special(command), count)
+            .await
+    }
+
+    pub async fn clear(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::Clear).await?;
+        self.delay_source.delay_us(self.timing.clear_us).await;
+        Ok(())
+    }
+
+    pub async fn home(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.special(&Command::Home).await
+    }
+
+    /// Moves the cursor to `col`, `row`, counting from zero.
+    ///
+    /// Positions off the configured geometry are rejected with
+    /// [`Error::OutOfBounds`] rather than spilling into another row.
+    pub async fn set_cursor(
+        &mut self,
+        col: u8,
+        row: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let address = self
+            .state
+            .geometry
+            .ddram_address(col, row)
+            .ok_or(Error::OutOfBounds { col, row })?;
+
+        self.special(&Command::SetCursor { address }).await
+    }
+
+    /// Writes raw bytes at the cursor, batched into as few bus transfers as
+    /// the display's receive buffer allows.
+    pub async fn write(
+        &mut self,
+        buf: &[u8],
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.begin_transmission().await?;
+        self.transmit_chunks(buf.chunks(MAX_CHUNK_LEN)).await?;
+        self.end_transmission().await?;
+
+        self.delay_source.delay_us(self.timing.write_us).await;
+
+        Ok(())
+    }
+
+    pub async fn write_str(
+        &mut self,
+        s: &str,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        if !s.is_empty() {
+            self.write(s.as_bytes()).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn no_display(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control &= !LCD_DISPLAYON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+            .await
+    }
+
+    pub async fn display(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control |= LCD_DISPLAYON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+            .await
+    }
+
+    pub async fn no_cursor(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control &= !LCD_CURSORON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+            .await
+    }
+
+    pub async fn cursor(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control |= LCD_CURSORON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+            .await
+    }
+
+    /// Sets the backlight color in one go using the fast RGB command.
+    pub async fn set_backlight(
+        &mut self,
+        r: u8,
+        g: u8,
+        b: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight = [Some(r), Some(g), Some(b)];
+
+        self.setting(&Command::Rgb(r, g, b)).await
+    }
+
+    /// Stops the cursor block from blinking.
+    pub async fn no_blink(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control &= !LCD_BLINKON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+            .await
+    }
+
+    /// Makes the cursor block blink.
+    pub async fn blink(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control |= LCD_BLINKON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+            .await
+    }
+
+    /// Makes text flow left to right from the cursor.
+    pub async fn left_to_right(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode |= LCD_ENTRYLEFT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+            .await
+    }
+
+    /// Makes text flow right to left from the cursor.
+    pub async fn right_to_left(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode &= !LCD_ENTRYLEFT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+            .await
+    }
+
+    /// Shifts the display on every write so text appears to grow from
+    /// a fixed cursor position, e.g. for right-aligned numeric entry.
+    pub async fn autoscroll(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode |= LCD_ENTRYSHIFTINCREMENT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+            .await
+    }
+
+    /// Keeps the display fixed while the cursor advances.
+    pub async fn no_autoscroll(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode &= !LCD_ENTRYSHIFTINCREMENT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+            .await
+    }
+
+    /// Scrolls the whole display left by `count` characters.
+    pub async fn scroll_display_left(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_DISPLAYMOVE | LCD_MOVELEFT), count)
+            .await
+    }
+
+    /// Scrolls the whole display right by `count` characters.
+    pub async fn scroll_display_right(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_DISPLAYMOVE | LCD_MOVERIGHT), count)
+            .await
+    }
+
+    /// Moves the cursor left by `count` characters.
+    pub async fn move_cursor_left(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_CURSORMOVE | LCD_MOVELEFT), count)
+            .await
+    }
+
+    /// Moves the cursor right by `count` characters.
+    pub async fn move_cursor_right(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_CURSORMOVE | LCD_MOVERIGHT), count)
+            .await
+    }
+
+    /// Sets the display contrast; lower values give darker characters.
+    ///
+    /// The display stores the value in EEPROM, so this waits for the EEPROM
+    /// write delay in [`Timing`] before the next command.
+    pub async fn set_contrast(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.contrast = Some(value);
+
+        self.eeprom_setting(&Command::Contrast(value)).await
+    }
+
+    /// Stores a custom 5x8 glyph in one of the eight CGRAM slots.
+    ///
+    /// Each byte of `bitmap` is one row, top first, using its low five bits.
+    pub async fn create_char(
+        &mut self,
+        slot: u8,
+        bitmap: [u8; 8],
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        check_slot(slot)?;
+
+        self.eeprom_setting(&Command::CreateChar { slot, bitmap })
+            .await
+    }
+
+    /// Prints the custom glyph stored in `slot` at the cursor.
+    pub async fn write_char(
+        &mut self,
+        slot: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        check_slot(slot)?;
+        self.setting(&Command::WriteChar(slot)).await
+    }
+
+    /// Shows the splash screen when the display boots.
+    pub async fn enable_splash(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::EnableSplash).await
+    }
+
+    /// Skips the splash screen when the display boots.
+    pub async fn disable_splash(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::DisableSplash).await
+    }
+
+    /// Saves the current screen contents as the boot splash screen.
+    ///
+    /// The contents are written to EEPROM, which takes a few hundred
+    /// milliseconds, so this waits for the write to finish.
+    pub async fn save_splash(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::SaveSplash).await?;
+        self.delay_source.delay_us(self.timing.splash_save_us).await;
+        Ok(())
+    }
+
+    /// Shows the firmware's confirmation messages, e.g. "Contrast: 10", after
+    /// setting changes.
+    pub async fn enable_system_messages(
+        &mut self,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::EnableSystemMessages).await
+    }
+
+    /// Suppresses the firmware's confirmation messages after setting changes.
+    pub async fn disable_system_messages(
+        &mut self,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::DisableSystemMessages).await
+    }
+
+    /// Switches the display's UART to a new baud rate.
+    ///
+    /// The display keeps the rate across resets. When driving it over UART,
+    /// reconfigure the host port to match before sending anything else.
+    pub async fn set_baud_rate(
+        &mut self,
+        rate: BaudRate,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::BaudRate(rate)).await
+    }
+
+    /// Reboots the display firmware, waits for it to come back up and
+    /// pushes the cached state again.
+    pub async fn reset(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::Reset).await?;
+        self.reinit().await
+    }
+
+    /// Sets the primary (red) backlight brightness, scaled from 0-255.
+    pub async fn set_primary_brightness(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight[0] = Some(value);
+        self.setting(&Command::PrimaryBrightness(brightness_step(value)))
+            .await
+    }
+
+    /// Sets the green backlight brightness, scaled from 0-255.
+    pub async fn set_green_brightness(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight[1] = Some(value);
+        self.setting(&Command::GreenBrightness(brightness_step(value)))
+            .await
+    }
+
+    /// Sets the blue backlight brightness, scaled from 0-255.
+    pub async fn set_blue_brightness(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight[2] = Some(value);
+        self.setting(&Command::BlueBrightness(brightness_step(value)))
+            .await
+    }
+
+    async fn send_address_command(
+        &mut self,
+        address: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        check_address(address)?;
+
+        self.eeprom_setting(&Command::Address(address)).await
+    }
+
+    /// Sends a SerLCD setting and waits for the display to apply it.
+    async fn setting(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command(command).await?;
+        self.delay_source.delay_us(self.timing.command_us).await;
+        Ok(())
+    }
+
+    /// Sends a SerLCD setting the display stores in EEPROM and waits for the
+    /// write to finish.
+    async fn eeprom_setting(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command(command).await?;
+        self.delay_source
+            .delay_us(self.timing.eeprom_write_us)
+            .await;
+        Ok(())
+    }
+
+    /// Sends an HD44780 instruction and waits for the display to apply it.
+    async fn special(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command(command).await?;
+        self.delay_source
+            .delay_us(self.timing.special_command_us)
+            .await;
+        Ok(())
+    }
+
+    /// Repeats `command` within one transmission, split into chunks of whole
+    /// frames, then waits once for the display to process them all.
+    async fn send_command_count(
+        &mut self,
+        command: &Command,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let repeat = Repeat::new(command, count);
+
+        self.begin_transmission().await?;
+        self.transmit_chunks(repeat.chunks()).await?;
+        self.end_transmission().await?;
+
+        self.delay_source
+            .delay_us(self.timing.special_command_us)
+            .await;
+
+        Ok(())
+    }
+
+    /// Encodes `command` and sends it as a single transmission.
+    async fn send_command(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let frame = Frame::single(command);
+
+        self.begin_transmission().await?;
+        self.transmit(frame.as_slice()).await?;
+        self.end_transmission().await
+    }
+
+    async fn begin_transmission(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.interface.begin_transmission().await?;
+
+        self.delay_source
+            .delay_us(self.timing.transmission_setup_us)
+            .await;
+
+        Ok(())
+    }
+
+    async fn end_transmission(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.interface.end_transmission().await?;
+
+        self.delay_source
+            .delay_us(self.timing.transmission_teardown_us)
+            .await;
+
+        Ok(())
+    }
+
+    /// Sends each chunk as its own transfer, pausing between them so the
+    /// display can drain its receive buffer.
+    async fn transmit_chunks<'a>(
+        &mut self,
+        chunks: impl Iterator<Item = &'a [u8]>,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        for (i, chunk) in chunks.enumerate() {
+            if i > 0 {
+                self.delay_source.delay_us(self.timing.chunk_us).await;
+            }
+
+            self.transmit(chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn transmit(
+        &mut self,
+        data: &[u8],
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.interface.send(data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    use super::*;
+    use crate::emulator::{Emulator, EmulatorSpi, NoDelay};
+
+    /// Polls `future` to completion; nothing in these tests ever waits.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn drives_the_emulator() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd16x2));
+        let mut lcd = AsyncSerLCD::new_spi_device(EmulatorSpi::new(&emulator), NoDelay)
+            .with_geometry(Geometry::Lcd16x2);
+
+        block_on(async {
+            lcd.setup().await.unwrap();
+            lcd.write_str("Hello").await.unwrap();
+            lcd.set_cursor(2, 1).await.unwrap();
+            lcd.write_str("async").await.unwrap();
+            lcd.blink().await.unwrap();
+            lcd.move_cursor_left(3).await.unwrap();
+        });
+
+        let emulator = emulator.borrow();
+        assert_eq!(&*emulator.row(0), b"Hello           ");
+        assert_eq!(&*emulator.row(1), b"  async         ");
+        assert_eq!(emulator.cursor(), Some((4, 1)));
+        assert!(emulator.blink_on());
+    }
+}
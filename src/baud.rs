@@ -0,0 +1,81 @@
+//! UART baud rates supported by the SerLCD firmware.
+
+/// A baud rate the display's UART can be switched to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BaudRate {
+    B1200,
+    B2400,
+    B4800,
+    #[default]
+    B9600,
+    B14400,
+    B19200,
+    B38400,
+    B57600,
+    B115200,
+    B230400,
+    B460800,
+    B921600,
+    B1000000,
+}
+
+impl BaudRate {
+    pub fn bits_per_second(&self) -> u32 {
+        match self {
+            BaudRate::B1200 => 1200,
+            BaudRate::B2400 => 2400,
+            BaudRate::B4800 => 4800,
+            BaudRate::B9600 => 9600,
+            BaudRate::B14400 => 14400,
+            BaudRate::B19200 => 19200,
+            BaudRate::B38400 => 38400,
+            BaudRate::B57600 => 57600,
+            BaudRate::B115200 => 115200,
+            BaudRate::B230400 => 230400,
+            BaudRate::B460800 => 460800,
+            BaudRate::B921600 => 921600,
+            BaudRate::B1000000 => 1000000,
+        }
+    }
+
+    /// Setting command selecting this baud rate.
+    pub(crate) fn command(&self) -> u8 {
+        match self {
+            BaudRate::B2400 => 0x0b,
+            BaudRate::B4800 => 0x0c,
+            BaudRate::B9600 => 0x0d,
+            BaudRate::B14400 => 0x0e,
+            BaudRate::B19200 => 0x0f,
+            BaudRate::B38400 => 0x10,
+            BaudRate::B57600 => 0x11,
+            BaudRate::B115200 => 0x12,
+            BaudRate::B230400 => 0x13,
+            BaudRate::B460800 => 0x14,
+            BaudRate::B921600 => 0x15,
+            BaudRate::B1000000 => 0x16,
+            BaudRate::B1200 => 0x17,
+        }
+    }
+
+    /// Baud rate selected by a setting command, if it is one.
+    pub(crate) fn from_command(command: u8) -> Option<BaudRate> {
+        let rate = match command {
+            0x0b => BaudRate::B2400,
+            0x0c => BaudRate::B4800,
+            0x0d => BaudRate::B9600,
+            0x0e => BaudRate::B14400,
+            0x0f => BaudRate::B19200,
+            0x10 => BaudRate::B38400,
+            0x11 => BaudRate::B57600,
+            0x12 => BaudRate::B115200,
+            0x13 => BaudRate::B230400,
+            0x14 => BaudRate::B460800,
+            0x15 => BaudRate::B921600,
+            0x16 => BaudRate::B1000000,
+            0x17 => BaudRate::B1200,
+            _ => return None,
+        };
+
+        Some(rate)
+    }
+}
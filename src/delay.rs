@@ -0,0 +1,86 @@
+//! Adapters between the delay traits of different embedded-hal versions.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal_02::blocking::delay::DelayMs;
+
+/// Wraps an embedded-hal 0.2 millisecond delay so it can drive the display.
+///
+/// Sub-millisecond delays are rounded up to whole milliseconds.
+pub struct LegacyDelay<D> {
+    delay: D,
+}
+
+impl<D> LegacyDelay<D> {
+    pub fn new(delay: D) -> Self {
+        Self { delay }
+    }
+
+    pub fn release(self) -> D {
+        self.delay
+    }
+}
+
+impl<D> DelayNs for LegacyDelay<D>
+where
+    D: DelayMs<u8>,
+{
+    fn delay_ns(&mut self, ns: u32) {
+        self.delay_ms(ns.div_ceil(1_000_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.delay_ms(us.div_ceil(1_000));
+    }
+
+    fn delay_ms(&mut self, mut ms: u32) {
+        while ms > 0 {
+            let chunk = core::cmp::min(ms, u8::MAX as u32);
+            self.delay.delay_ms(chunk as u8);
+            ms -= chunk;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the length of every millisecond delay.
+    #[derive(Default)]
+    struct RecordingDelay {
+        calls: Vec<u8>,
+    }
+
+    impl DelayMs<u8> for RecordingDelay {
+        fn delay_ms(&mut self, ms: u8) {
+            self.calls.push(ms);
+        }
+    }
+
+    fn calls(f: impl FnOnce(&mut LegacyDelay<RecordingDelay>)) -> Vec<u8> {
+        let mut delay = LegacyDelay::new(RecordingDelay::default());
+        f(&mut delay);
+        delay.release().calls
+    }
+
+    #[test]
+    fn sub_millisecond_delays_round_up() {
+        assert_eq!(calls(|d| d.delay_us(1)), [1]);
+        assert_eq!(calls(|d| d.delay_us(1_001)), [2]);
+        assert_eq!(calls(|d| d.delay_ns(1)), [1]);
+    }
+
+    #[test]
+    fn zero_delays_do_not_sleep() {
+        assert_eq!(calls(|d| d.delay_us(0)), []);
+        assert_eq!(calls(|d| d.delay_ms(0)), []);
+    }
+
+    #[test]
+    fn long_delays_are_split_into_u8_pieces() {
+        let mut expected = vec![u8::MAX; 7];
+        expected.push(215);
+
+        assert_eq!(calls(|d| d.delay_ms(2000)), expected);
+    }
+}
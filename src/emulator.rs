@@ -0,0 +1,752 @@
+//! Host-side model of a SerLCD for testing code that drives the display.
+//!
+//! [`Emulator`] follows the OpenLCD firmware closely enough to tell what the
+//! glass would show: it keeps the HD44780 DDRAM and CGRAM, the cursor, the
+//! entry mode and display flags, and the contrast and backlight settings.
+//! [`EmulatorSpi`] and [`EmulatorCs`] feed it from a driver, and [`NoDelay`]
+//! lets the driver run without sleeping.
+//!
+//! Transient system messages such as "Contrast: 40" are not shown, and the
+//! splash screen is only tracked as a flag.
+//!
+//! With the `std` feature, [`TerminalRenderer`] shows the emulated glass in a
+//! terminal.
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::ops::Deref;
+
+use crate::protocol::{Command, Decoded, Decoder};
+use crate::*;
+
+#[cfg(feature = "std")]
+mod terminal;
+
+#[cfg(feature = "std")]
+pub use terminal::{render, TerminalRenderer};
+
+/// Characters in each of the two DDRAM lines of the HD44780.
+const LINE_LEN: u8 = 40;
+const MAX_COLUMNS: usize = 20;
+
+const DEFAULT_CONTRAST: u8 = 40;
+const DEFAULT_DISPLAY_CONTROL: u8 = LCD_DISPLAYON | LCD_CURSOROFF | LCD_BLINKOFF;
+const DEFAULT_ENTRY_MODE: u8 = LCD_ENTRYLEFT | LCD_ENTRYSHIFTDECREMENT;
+
+/// A simulated display.
+#[derive(Debug, Clone)]
+pub struct Emulator {
+    decoder: Decoder,
+    geometry: Geometry,
+    ddram: [[u8; LINE_LEN as usize]; 2],
+    cgram: [[u8; 8]; CUSTOM_CHAR_SLOTS as usize],
+    /// DDRAM address the next character is written to.
+    address_counter: u8,
+    /// Characters the display has been shifted left by.
+    shift: u8,
+    display_control: u8,
+    entry_mode: u8,
+    contrast: u8,
+    backlight: (u8, u8, u8),
+    address: u8,
+    baud_rate: BaudRate,
+    splash: bool,
+    system_messages: bool,
+    selected: bool,
+}
+
+impl Emulator {
+    /// A freshly powered display with the given panel.
+    pub fn new(geometry: Geometry) -> Self {
+        Self {
+            decoder: Decoder::new(),
+            geometry,
+            ddram: [[b' '; LINE_LEN as usize]; 2],
+            cgram: [[0; 8]; CUSTOM_CHAR_SLOTS as usize],
+            address_counter: 0,
+            shift: 0,
+            display_control: DEFAULT_DISPLAY_CONTROL,
+            entry_mode: DEFAULT_ENTRY_MODE,
+            contrast: DEFAULT_CONTRAST,
+            backlight: (255, 255, 255),
+            address: DISPLAY_ADDRESS,
+            baud_rate: BaudRate::default(),
+            splash: true,
+            system_messages: true,
+            selected: false,
+        }
+    }
+
+    /// Feeds bytes as if they arrived on the bus.
+    pub fn receive(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match self.decoder.push(byte) {
+                Some(Decoded::Char(c)) => self.put_char(c),
+                Some(Decoded::Command(command)) => self.execute(command),
+                None => {}
+            }
+        }
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Character code shown at `col`, `row`, or `None` off the panel.
+    pub fn char_at(&self, col: u8, row: u8) -> Option<u8> {
+        let address = self.geometry.ddram_address(col, row)?;
+        let (line, pos) = split_address(address);
+
+        Some(self.ddram[line][((pos + self.shift) % LINE_LEN) as usize])
+    }
+
+    /// Character codes shown on `row`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is off the panel.
+    pub fn row(&self, row: u8) -> Row {
+        assert!(row < self.geometry.rows(), "row {} is off the panel", row);
+
+        let mut chars = [b' '; MAX_COLUMNS];
+        let len = self.geometry.columns();
+
+        for col in 0..len {
+            chars[col as usize] = self.char_at(col, row).unwrap_or(b' ');
+        }
+
+        Row {
+            chars,
+            len: len as usize,
+        }
+    }
+
+    /// Position of the cursor on the glass, or `None` if it has moved to a
+    /// part of DDRAM that is not shown.
+    pub fn cursor(&self) -> Option<(u8, u8)> {
+        let (line, pos) = split_address(self.address_counter);
+        let offsets = self.geometry.row_offsets();
+
+        (0..self.geometry.rows()).find_map(|row| {
+            let (row_line, start) = split_address(offsets[row as usize]);
+            let col = (pos + 2 * LINE_LEN - start - self.shift) % LINE_LEN;
+
+            (row_line == line && col < self.geometry.columns()).then_some((col, row))
+        })
+    }
+
+    pub fn display_on(&self) -> bool {
+        self.display_control & LCD_DISPLAYON != 0
+    }
+
+    /// Whether the underline cursor is shown.
+    pub fn cursor_on(&self) -> bool {
+        self.display_control & LCD_CURSORON != 0
+    }
+
+    /// Whether the cursor block blinks.
+    pub fn blink_on(&self) -> bool {
+        self.display_control & LCD_BLINKON != 0
+    }
+
+    pub fn left_to_right(&self) -> bool {
+        self.entry_mode & LCD_ENTRYLEFT != 0
+    }
+
+    pub fn autoscroll(&self) -> bool {
+        self.entry_mode & LCD_ENTRYSHIFTINCREMENT != 0
+    }
+
+    /// Glyph stored in a custom character slot, one row of five pixels per
+    /// byte.
+    pub fn glyph(&self, slot: u8) -> [u8; 8] {
+        self.cgram[(slot % CUSTOM_CHAR_SLOTS) as usize]
+    }
+
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// Red, green and blue backlight levels.
+    pub fn backlight(&self) -> (u8, u8, u8) {
+        self.backlight
+    }
+
+    /// I2C address the display answers to.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    pub fn baud_rate(&self) -> BaudRate {
+        self.baud_rate
+    }
+
+    pub fn splash_enabled(&self) -> bool {
+        self.splash
+    }
+
+    pub fn system_messages_enabled(&self) -> bool {
+        self.system_messages
+    }
+
+    fn put_char(&mut self, c: u8) {
+        let (line, pos) = split_address(self.address_counter);
+        self.ddram[line][pos as usize] = c;
+
+        let left_to_right = self.left_to_right();
+
+        // The firmware moves on to the next row of the panel rather than the
+        // next DDRAM line once a row is full.
+        self.address_counter = match self.logical_position(self.address_counter) {
+            Some((col, row)) if left_to_right && col + 1 == self.geometry.columns() => {
+                let next = (row + 1) % self.geometry.rows();
+                self.geometry.row_offsets()[next as usize]
+            }
+            _ => step_address(self.address_counter, left_to_right),
+        };
+
+        if self.autoscroll() {
+            self.shift_display(left_to_right);
+        }
+    }
+
+    fn execute(&mut self, command: Command) {
+        match command {
+            Command::Clear | Command::Special(0x01) => self.clear(),
+            Command::Home => {
+                self.address_counter = 0;
+                self.shift = 0;
+            }
+            Command::SetCursor { address } => self.address_counter = address,
+            Command::DisplayControl(flags) => self.display_control = flags,
+            Command::EntryMode(flags) => self.entry_mode = flags,
+            Command::Shift(flags) => {
+                let left = flags & LCD_MOVERIGHT == 0;
+
+                if flags & LCD_DISPLAYMOVE != 0 {
+                    self.shift_display(left);
+                } else {
+                    self.address_counter = step_address(self.address_counter, !left);
+                }
+            }
+            Command::Contrast(value) => self.contrast = value,
+            Command::Address(address) => self.address = address,
+            Command::Rgb(r, g, b) => self.backlight = (r, g, b),
+            Command::PrimaryBrightness(step) => self.backlight.0 = brightness(step),
+            Command::GreenBrightness(step) => self.backlight.1 = brightness(step),
+            Command::BlueBrightness(step) => self.backlight.2 = brightness(step),
+            Command::CreateChar { slot, mut bitmap } => {
+                for row in bitmap.iter_mut() {
+                    *row &= 0x1f;
+                }
+                self.cgram[slot as usize] = bitmap;
+            }
+            Command::WriteChar(slot) => self.put_char(slot),
+            Command::Width16 => self.resize(16, self.geometry.rows()),
+            Command::Width20 => self.resize(20, self.geometry.rows()),
+            Command::Lines1 => self.resize(self.geometry.columns(), 1),
+            Command::Lines2 => self.resize(self.geometry.columns(), 2),
+            Command::Lines4 => self.resize(self.geometry.columns(), 4),
+            Command::EnableSystemMessages => self.system_messages = true,
+            Command::DisableSystemMessages => self.system_messages = false,
+            Command::EnableSplash => self.splash = true,
+            Command::DisableSplash => self.splash = false,
+            Command::SaveSplash => {}
+            Command::BaudRate(rate) => self.baud_rate = rate,
+            Command::Reset => self.reboot(),
+            Command::Special(_) | Command::Setting(_) => {}
+        }
+    }
+
+    fn clear(&mut self) {
+        self.ddram = [[b' '; LINE_LEN as usize]; 2];
+        self.address_counter = 0;
+        self.shift = 0;
+    }
+
+    fn resize(&mut self, columns: u8, rows: u8) {
+        if let Some(geometry) = Geometry::from_size(columns, rows) {
+            self.geometry = geometry;
+            self.clear();
+        }
+    }
+
+    /// Restarts the firmware, keeping the settings it stores in EEPROM.
+    fn reboot(&mut self) {
+        self.decoder.reset();
+        self.clear();
+        self.display_control = DEFAULT_DISPLAY_CONTROL;
+        self.entry_mode = DEFAULT_ENTRY_MODE;
+    }
+
+    fn shift_display(&mut self, left: bool) {
+        self.shift = if left {
+            (self.shift + 1) % LINE_LEN
+        } else {
+            (self.shift + LINE_LEN - 1) % LINE_LEN
+        };
+    }
+
+    /// Unshifted panel position of a DDRAM address.
+    fn logical_position(&self, address: u8) -> Option<(u8, u8)> {
+        (0..self.geometry.rows()).find_map(|row| {
+            let start = self.geometry.row_offsets()[row as usize];
+            let col = address.wrapping_sub(start);
+
+            (col < self.geometry.columns()).then_some((col, row))
+        })
+    }
+}
+
+/// One row of an [`Emulator`]'s panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Row {
+    chars: [u8; MAX_COLUMNS],
+    len: usize,
+}
+
+impl Deref for Row {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.chars[..self.len]
+    }
+}
+
+/// Splits a DDRAM address into its line and position in that line.
+fn split_address(address: u8) -> (usize, u8) {
+    (((address >> 6) & 1) as usize, (address & 0x3f) % LINE_LEN)
+}
+
+/// Address after moving the HD44780 address counter by one character.
+fn step_address(address: u8, forward: bool) -> u8 {
+    let (line, pos) = split_address(address);
+
+    let (line, pos) = match (forward, pos) {
+        (true, p) if p + 1 == LINE_LEN => (line ^ 1, 0),
+        (true, p) => (line, p + 1),
+        (false, 0) => (line ^ 1, LINE_LEN - 1),
+        (false, p) => (line, p - 1),
+    };
+
+    ((line as u8) << 6) | pos
+}
+
+/// Backlight level of a 30-step brightness setting.
+fn brightness(step: u8) -> u8 {
+    (step as u16 * 255 / (BRIGHTNESS_STEPS - 1) as u16) as u8
+}
+
+/// SPI bus feeding an [`Emulator`].
+///
+/// As a bus the display only listens while an [`EmulatorCs`] on the same
+/// emulator is low. As an embedded-hal 1.0 [`SpiDevice`] every transaction
+/// reaches the display.
+///
+/// [`SpiDevice`]: embedded_hal::spi::SpiDevice
+pub struct EmulatorSpi<'a> {
+    lcd: &'a RefCell<Emulator>,
+}
+
+impl<'a> EmulatorSpi<'a> {
+    pub fn new(lcd: &'a RefCell<Emulator>) -> Self {
+        Self { lcd }
+    }
+}
+
+impl embedded_hal::spi::ErrorType for EmulatorSpi<'_> {
+    type Error = Infallible;
+}
+
+impl embedded_hal::spi::SpiDevice for EmulatorSpi<'_> {
+    fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), Infallible> {
+        use embedded_hal::spi::Operation;
+
+        let mut lcd = self.lcd.borrow_mut();
+
+        for operation in operations {
+            match operation {
+                Operation::Write(words) => lcd.receive(words),
+                Operation::Transfer(read, write) => {
+                    lcd.receive(write);
+                    read.fill(0);
+                }
+                Operation::TransferInPlace(words) => {
+                    lcd.receive(words);
+                    words.fill(0);
+                }
+                Operation::Read(words) => words.fill(0),
+                Operation::DelayNs(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiDevice for EmulatorSpi<'_> {
+    async fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), Infallible> {
+        embedded_hal::spi::SpiDevice::transaction(self, operations)
+    }
+}
+
+impl embedded_hal::spi::SpiBus for EmulatorSpi<'_> {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        words.fill(0);
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        let mut lcd = self.lcd.borrow_mut();
+
+        if lcd.selected {
+            lcd.receive(words);
+        }
+
+        Ok(())
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Infallible> {
+        embedded_hal::spi::SpiBus::write(self, write)?;
+        read.fill(0);
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        embedded_hal::spi::SpiBus::write(self, words)?;
+        words.fill(0);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh02")]
+impl embedded_hal_02::blocking::spi::Write<u8> for EmulatorSpi<'_> {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        embedded_hal::spi::SpiBus::write(self, words)
+    }
+}
+
+#[cfg(feature = "eh02")]
+impl embedded_hal_02::blocking::spi::Transfer<u8> for EmulatorSpi<'_> {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Infallible> {
+        embedded_hal_02::blocking::spi::Write::write(self, words)?;
+        words.fill(0);
+        Ok(words)
+    }
+}
+
+/// Chip select line of an [`Emulator`], active low.
+pub struct EmulatorCs<'a> {
+    lcd: &'a RefCell<Emulator>,
+}
+
+impl<'a> EmulatorCs<'a> {
+    pub fn new(lcd: &'a RefCell<Emulator>) -> Self {
+        Self { lcd }
+    }
+}
+
+impl embedded_hal::digital::ErrorType for EmulatorCs<'_> {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for EmulatorCs<'_> {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.lcd.borrow_mut().selected = true;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.lcd.borrow_mut().selected = false;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh02")]
+impl embedded_hal_02::digital::v2::OutputPin for EmulatorCs<'_> {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.lcd.borrow_mut().selected = true;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.lcd.borrow_mut().selected = false;
+        Ok(())
+    }
+}
+
+/// Delay that returns immediately, since an [`Emulator`] needs no settling
+/// time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoDelay;
+
+impl embedded_hal::delay::DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[cfg(feature = "eh02")]
+impl embedded_hal_02::blocking::delay::DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<F>(geometry: Geometry, f: F) -> Emulator
+    where
+        F: FnOnce(&mut SerLCD<interface::eh1::SpiInterface<EmulatorSpi<'_>>, NoDelay>),
+    {
+        let emulator = RefCell::new(Emulator::new(geometry));
+
+        {
+            let mut lcd = SerLCD::new_spi_device(EmulatorSpi::new(&emulator), NoDelay)
+                .with_geometry(geometry);
+
+            lcd.setup().unwrap();
+            f(&mut lcd);
+        }
+
+        emulator.into_inner()
+    }
+
+    #[test]
+    fn text_lands_on_the_addressed_rows() {
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.write_str("Hello").unwrap();
+            lcd.set_cursor(3, 1).unwrap();
+            lcd.write_str("row 1").unwrap();
+            lcd.set_cursor(0, 3).unwrap();
+            lcd.write_str("last").unwrap();
+        });
+
+        assert_eq!(&*lcd.row(0), b"Hello               ");
+        assert_eq!(&*lcd.row(1), b"   row 1            ");
+        assert_eq!(&*lcd.row(2), b"                    ");
+        assert_eq!(&*lcd.row(3), b"last                ");
+        assert_eq!(lcd.cursor(), Some((4, 3)));
+    }
+
+    #[test]
+    fn full_rows_wrap_in_panel_order() {
+        let lcd = run(Geometry::Lcd16x2, |lcd| {
+            lcd.write_str("0123456789abcdefXY").unwrap();
+        });
+
+        assert_eq!(&*lcd.row(0), b"0123456789abcdef");
+        assert_eq!(&*lcd.row(1), b"XY              ");
+    }
+
+    #[test]
+    fn clear_blanks_the_panel_and_homes_the_cursor() {
+        let lcd = run(Geometry::Lcd20x2, |lcd| {
+            lcd.set_cursor(5, 1).unwrap();
+            lcd.write_str("gone").unwrap();
+            lcd.clear().unwrap();
+        });
+
+        assert_eq!(&*lcd.row(1), b"                    ");
+        assert_eq!(lcd.cursor(), Some((0, 0)));
+    }
+
+    #[test]
+    fn display_flags_and_entry_mode_are_tracked() {
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.cursor().unwrap();
+            lcd.blink().unwrap();
+            lcd.no_display().unwrap();
+            lcd.right_to_left().unwrap();
+            lcd.set_cursor(5, 0).unwrap();
+            lcd.write_str("ab").unwrap();
+        });
+
+        assert!(!lcd.display_on());
+        assert!(lcd.cursor_on());
+        assert!(lcd.blink_on());
+        assert!(!lcd.left_to_right());
+        assert_eq!(&lcd.row(0)[3..6], b" ba");
+        assert_eq!(lcd.cursor(), Some((3, 0)));
+    }
+
+    #[test]
+    fn scrolling_shifts_every_row() {
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.write_str("abc").unwrap();
+            lcd.set_cursor(0, 2).unwrap();
+            lcd.write_str("xyz").unwrap();
+            lcd.scroll_display_left(1).unwrap();
+        });
+
+        assert_eq!(&lcd.row(0)[..3], b"bc ");
+        assert_eq!(&lcd.row(0)[19..], b"x");
+        assert_eq!(&lcd.row(2)[..2], b"yz");
+    }
+
+    #[test]
+    fn repeated_shifts_span_several_chunks() {
+        // 20 and 17 two byte shifts both overflow a single MAX_CHUNK_LEN
+        // transfer.
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.write_str("abc").unwrap();
+            lcd.set_cursor(0, 2).unwrap();
+            lcd.write_str("xyz").unwrap();
+            lcd.scroll_display_left(20).unwrap();
+        });
+
+        assert_eq!(&lcd.row(0)[..3], b"xyz");
+
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.set_cursor(0, 1).unwrap();
+            lcd.move_cursor_right(17).unwrap();
+            lcd.move_cursor_right(0).unwrap();
+        });
+
+        assert_eq!(lcd.cursor(), Some((17, 1)));
+    }
+
+    #[test]
+    fn custom_characters_are_stored_and_printed() {
+        let bitmap = [0x1f, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1f];
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.create_char(2, bitmap).unwrap();
+            lcd.write_char(2).unwrap();
+        });
+
+        assert_eq!(lcd.glyph(2), bitmap);
+        assert_eq!(lcd.char_at(0, 0), Some(2));
+    }
+
+    #[test]
+    fn backlight_and_contrast_are_tracked() {
+        let lcd = run(Geometry::Lcd20x4, |lcd| {
+            lcd.set_backlight(10, 20, 30).unwrap();
+            lcd.set_green_brightness(255).unwrap();
+            lcd.set_contrast(0x7c).unwrap();
+        });
+
+        assert_eq!(lcd.backlight(), (10, 255, 30));
+        assert_eq!(lcd.contrast(), 0x7c);
+    }
+
+    #[test]
+    fn geometry_follows_setup() {
+        let lcd = run(Geometry::Lcd16x4, |_| {});
+        assert_eq!(lcd.geometry(), Geometry::Lcd16x4);
+    }
+
+    #[test]
+    fn function_set_and_cgram_address_leave_the_glass_alone() {
+        let mut lcd = Emulator::new(Geometry::Lcd20x4);
+        lcd.receive(b"abc\xfe\x28\xfe\x38\xfe\x48");
+
+        assert!(lcd.display_on());
+        assert_eq!(&lcd.row(0)[..4], b"abc ");
+        assert_eq!(lcd.cursor(), Some((3, 0)));
+    }
+
+    #[test]
+    fn cursor_positions_off_the_panel_are_rejected() {
+        let lcd = run(Geometry::Lcd16x2, |lcd| {
+            lcd.set_cursor(4, 1).unwrap();
+
+            assert!(matches!(
+                lcd.set_cursor(16, 0),
+                Err(Error::OutOfBounds { col: 16, row: 0 })
+            ));
+            assert!(matches!(
+                lcd.set_cursor(0, 2),
+                Err(Error::OutOfBounds { col: 0, row: 2 })
+            ));
+        });
+
+        assert_eq!(lcd.cursor(), Some((4, 1)));
+    }
+
+    #[test]
+    fn long_writes_arrive_whole() {
+        let text = b"The quick brown fox jumps over the lazy dog, twice over";
+        assert!(text.len() > MAX_CHUNK_LEN);
+
+        let lcd = run(Geometry::Lcd20x4, |lcd| lcd.write(text).unwrap());
+
+        assert_eq!(&*lcd.row(0), &text[..20]);
+        assert_eq!(&*lcd.row(1), &text[20..40]);
+        assert_eq!(&lcd.row(2)[..text.len() - 40], &text[40..]);
+    }
+
+    #[test]
+    fn formatted_text_is_printed() {
+        use core::fmt::Write;
+
+        let lcd = run(Geometry::Lcd16x2, |lcd| {
+            write!(lcd, "T={:.1}C", 21.5).unwrap();
+        });
+
+        assert_eq!(&*lcd.row(0), b"T=21.5C         ");
+        assert_eq!(lcd.cursor(), Some((7, 0)));
+    }
+
+    #[test]
+    fn reset_restores_the_cached_state() {
+        let lcd = run(Geometry::Lcd20x2, |lcd| {
+            lcd.cursor().unwrap();
+            lcd.blink().unwrap();
+            lcd.right_to_left().unwrap();
+            lcd.set_backlight(1, 2, 3).unwrap();
+            lcd.set_contrast(10).unwrap();
+            lcd.write_str("before").unwrap();
+
+            lcd.reset().unwrap();
+        });
+
+        assert!(lcd.display_on());
+        assert!(lcd.cursor_on());
+        assert!(lcd.blink_on());
+        assert!(!lcd.left_to_right());
+        assert_eq!(lcd.backlight(), (1, 2, 3));
+        assert_eq!(lcd.contrast(), 10);
+        assert_eq!(&*lcd.row(0), b"                    ");
+        assert_eq!(lcd.geometry(), Geometry::Lcd20x2);
+    }
+
+    #[cfg(feature = "eh02")]
+    #[test]
+    fn legacy_spi_only_listens_while_selected() {
+        use embedded_hal_02::blocking::spi::Write;
+
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+
+        EmulatorSpi::new(&emulator).write(b"lost").unwrap();
+
+        let mut lcd = SerLCD::new(
+            EmulatorSpi::new(&emulator),
+            EmulatorCs::new(&emulator),
+            NoDelay,
+        );
+        lcd.write_str("kept").unwrap();
+
+        assert_eq!(&emulator.borrow().row(0)[..8], b"kept    ");
+    }
+}
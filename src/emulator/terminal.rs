@@ -0,0 +1,185 @@
+//! Draws an [`Emulator`] into a terminal with ANSI escape sequences.
+
+use std::io::{self, Write};
+
+use super::Emulator;
+
+/// Quadrant block characters, indexed by the lit quadrants: upper left 1,
+/// upper right 2, lower left 4, lower right 8.
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Pixel columns counted towards the left and right halves of a glyph; the
+/// middle column belongs to both.
+const LEFT_PIXELS: u8 = 0x1c;
+const RIGHT_PIXELS: u8 = 0x07;
+
+const TEXT_COLOR: (u8, u8, u8) = (16, 16, 16);
+/// Glass color with the backlight fully off, so text stays readable.
+const UNLIT_LEVEL: u16 = 48;
+
+/// Renders the glass of an [`Emulator`] as a framed box of colored text.
+///
+/// Each call to [`draw`](Self::draw) redraws over the previous drawing and
+/// advances the blink phase, so calling it a few times per second animates a
+/// blinking cursor much like the real display.
+#[derive(Debug, Default)]
+pub struct TerminalRenderer {
+    drawn_lines: usize,
+    blink_phase: bool,
+}
+
+impl TerminalRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn draw<W: Write>(&mut self, lcd: &Emulator, out: &mut W) -> io::Result<()> {
+        if self.drawn_lines > 0 {
+            write!(out, "\x1b[{}A\r", self.drawn_lines)?;
+        }
+
+        self.blink_phase = !self.blink_phase;
+        render(lcd, self.blink_phase, out)?;
+        out.flush()?;
+
+        self.drawn_lines = lcd.geometry().rows() as usize + 2;
+
+        Ok(())
+    }
+}
+
+/// Writes one frame of `lcd`, showing a blinking cursor block if
+/// `blink_phase` is set.
+pub fn render<W: Write>(lcd: &Emulator, blink_phase: bool, out: &mut W) -> io::Result<()> {
+    let geometry = lcd.geometry();
+    let border = "─".repeat(geometry.columns() as usize);
+    let (r, g, b) = glass_color(lcd.backlight());
+    let (tr, tg, tb) = TEXT_COLOR;
+    let cursor = lcd.cursor().filter(|_| lcd.display_on());
+
+    writeln!(out, "┌{}┐", border)?;
+
+    for row in 0..geometry.rows() {
+        write!(
+            out,
+            "│\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m",
+            r, g, b, tr, tg, tb
+        )?;
+
+        for col in 0..geometry.columns() {
+            let c = match lcd.char_at(col, row) {
+                Some(code) if lcd.display_on() => glyph_char(lcd, code),
+                _ => ' ',
+            };
+
+            if cursor == Some((col, row)) && lcd.blink_on() && blink_phase {
+                write!(out, "\x1b[7m{}\x1b[27m", c)?;
+            } else if cursor == Some((col, row)) && lcd.cursor_on() {
+                write!(out, "\x1b[4m{}\x1b[24m", c)?;
+            } else {
+                write!(out, "{}", c)?;
+            }
+        }
+
+        writeln!(out, "\x1b[0m│")?;
+    }
+
+    writeln!(out, "└{}┘", border)
+}
+
+/// Terminal character closest to a character code of the display.
+fn glyph_char(lcd: &Emulator, code: u8) -> char {
+    match code {
+        // CGRAM is mirrored at 0x08-0x0f.
+        0x00..=0x0f => quadrants(&lcd.glyph(code % 8)),
+        0x5c => '¥',
+        0x7e => '→',
+        0x7f => '←',
+        0x20..=0x7d => code as char,
+        0xdf => '°',
+        0xff => '█',
+        _ => '▯',
+    }
+}
+
+/// Approximates a 5x8 glyph with a quadrant block, lighting each quadrant
+/// that is at least half set.
+fn quadrants(bitmap: &[u8; 8]) -> char {
+    let lit = |rows: &[u8], mask: u8| {
+        let set: u32 = rows.iter().map(|row| (row & mask).count_ones()).sum();
+        set * 2 >= mask.count_ones() * rows.len() as u32
+    };
+
+    let (top, bottom) = bitmap.split_at(4);
+    let index = lit(top, LEFT_PIXELS) as usize
+        | (lit(top, RIGHT_PIXELS) as usize) << 1
+        | (lit(bottom, LEFT_PIXELS) as usize) << 2
+        | (lit(bottom, RIGHT_PIXELS) as usize) << 3;
+
+    QUADRANTS[index]
+}
+
+fn glass_color((r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    let scale = |level: u8| (UNLIT_LEVEL + level as u16 * (255 - UNLIT_LEVEL) / 255) as u8;
+    (scale(r), scale(g), scale(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Geometry;
+
+    fn rendered(lcd: &Emulator, blink_phase: bool) -> String {
+        let mut out = Vec::new();
+        render(lcd, blink_phase, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn glass_is_framed_and_colored_by_the_backlight() {
+        let mut lcd = Emulator::new(Geometry::Lcd16x2);
+        lcd.receive(b"\x7c\x2b\xff\x00\x00Hi");
+
+        let out = rendered(&lcd, false);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], format!("┌{}┐", "─".repeat(16)));
+        assert!(lines[1].contains("\x1b[48;2;255;48;48m"));
+        assert!(lines[1].contains("Hi              \x1b[0m│"));
+        assert_eq!(lines[3], format!("└{}┘", "─".repeat(16)));
+    }
+
+    #[test]
+    fn cursor_is_underlined_and_blinks() {
+        let mut lcd = Emulator::new(Geometry::Lcd16x2);
+        lcd.receive(b"ab\xfe\x0f");
+
+        assert!(rendered(&lcd, true).contains("ab\x1b[7m \x1b[27m"));
+        assert!(rendered(&lcd, false).contains("ab\x1b[4m \x1b[24m"));
+    }
+
+    #[test]
+    fn custom_glyphs_become_quadrant_blocks() {
+        assert_eq!(quadrants(&[0; 8]), ' ');
+        assert_eq!(quadrants(&[0x1f; 8]), '█');
+        assert_eq!(quadrants(&[0x18, 0x18, 0x18, 0x18, 0, 0, 0, 0]), '▘');
+        assert_eq!(quadrants(&[0, 0, 0, 0, 0x1f, 0x1f, 0x1f, 0x1f]), '▄');
+    }
+
+    #[test]
+    fn redraws_in_place() {
+        let lcd = Emulator::new(Geometry::Lcd20x4);
+        let mut renderer = TerminalRenderer::new();
+        let mut out = Vec::new();
+
+        renderer.draw(&lcd, &mut out).unwrap();
+        assert!(!out.starts_with(b"\x1b["));
+
+        out.clear();
+        renderer.draw(&lcd, &mut out).unwrap();
+        assert!(out.starts_with(b"\x1b[6A\r"));
+    }
+}
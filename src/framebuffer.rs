@@ -0,0 +1,329 @@
+//! Shadow framebuffer that only sends changed characters to the display.
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{Error, Interface, SerLCD};
+
+const MAX_COLUMNS: usize = 20;
+const MAX_ROWS: usize = 4;
+
+/// Unchanged cells between two changed runs that are cheaper to rewrite than
+/// to skip with another cursor move.
+const MERGE_GAP: usize = 3;
+
+/// A character grid in RAM layered over a [`SerLCD`].
+///
+/// Application code draws into the buffer, and [`flush`](Self::flush) sends
+/// only the runs of characters that differ from what is already on the glass.
+/// The buffer assumes the display is in its default left-to-right entry mode
+/// without autoscroll.
+pub struct FrameBuffer<IFACE: Interface, DS> {
+    lcd: SerLCD<IFACE, DS>,
+    cells: [[u8; MAX_COLUMNS]; MAX_ROWS],
+    shown: [[u8; MAX_COLUMNS]; MAX_ROWS],
+}
+
+impl<IFACE, DS> FrameBuffer<IFACE, DS>
+where
+    IFACE: Interface,
+    DS: DelayNs,
+{
+    /// Wraps a display that has been set up and is currently blank.
+    pub fn new(lcd: SerLCD<IFACE, DS>) -> Self {
+        Self {
+            lcd,
+            cells: [[b' '; MAX_COLUMNS]; MAX_ROWS],
+            shown: [[b' '; MAX_COLUMNS]; MAX_ROWS],
+        }
+    }
+
+    /// Gives access to the display, e.g. for backlight changes.
+    ///
+    /// Writing text or moving the cursor directly desynchronizes the buffer;
+    /// call [`invalidate`](Self::invalidate) afterwards.
+    pub fn lcd(&mut self) -> &mut SerLCD<IFACE, DS> {
+        &mut self.lcd
+    }
+
+    pub fn into_inner(self) -> SerLCD<IFACE, DS> {
+        self.lcd
+    }
+
+    /// Blanks the whole buffer.
+    pub fn clear(&mut self) {
+        self.cells = [[b' '; MAX_COLUMNS]; MAX_ROWS];
+    }
+
+    /// Puts a single character code at `col`, `row`.
+    pub fn draw_char(
+        &mut self,
+        col: u8,
+        row: u8,
+        c: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.check_position(col, row)?;
+        self.cells[row as usize][col as usize] = c;
+        Ok(())
+    }
+
+    /// Draws `s` starting at `col`, `row`, cutting it off at the end of the row.
+    pub fn draw_str(
+        &mut self,
+        col: u8,
+        row: u8,
+        s: &str,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.check_position(col, row)?;
+
+        let columns = self.lcd.geometry().columns() as usize;
+        let line = &mut self.cells[row as usize][col as usize..columns];
+
+        for (cell, b) in line.iter_mut().zip(s.bytes()) {
+            *cell = b;
+        }
+
+        Ok(())
+    }
+
+    /// Forgets what is on the glass so the next flush redraws everything,
+    /// e.g. after [`SerLCD::reinit`].
+    pub fn invalidate(&mut self) {
+        for (shown, cells) in self.shown.iter_mut().zip(self.cells.iter()) {
+            for (s, c) in shown.iter_mut().zip(cells.iter()) {
+                // Any value differing from the wanted one forces a rewrite.
+                *s = !*c;
+            }
+        }
+    }
+
+    /// Sends the changed parts of the buffer to the display.
+    pub fn flush(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let geometry = self.lcd.geometry();
+        let columns = geometry.columns() as usize;
+
+        for row in 0..geometry.rows() as usize {
+            let mut col = 0;
+
+            while let Some((start, end)) = self.next_run(row, col, columns) {
+                self.lcd.set_cursor(start as u8, row as u8)?;
+                self.lcd.write(&self.cells[row][start..end])?;
+                self.shown[row][start..end].copy_from_slice(&self.cells[row][start..end]);
+
+                col = end;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the next run of changed cells in `row` at or after `from`,
+    /// swallowing short unchanged gaps between changes.
+    fn next_run(&self, row: usize, from: usize, columns: usize) -> Option<(usize, usize)> {
+        let changed = |col: usize| self.cells[row][col] != self.shown[row][col];
+
+        let start = (from..columns).find(|&col| changed(col))?;
+        let mut end = start + 1;
+        let mut col = end;
+
+        while col < columns && col - end <= MERGE_GAP {
+            if changed(col) {
+                end = col + 1;
+            }
+            col += 1;
+        }
+
+        Some((start, end))
+    }
+
+    fn check_position(
+        &self,
+        col: u8,
+        row: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.lcd
+            .geometry()
+            .ddram_address(col, row)
+            .map(|_| ())
+            .ok_or(Error::OutOfBounds { col, row })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+
+    use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+    use super::*;
+    use crate::emulator::{Emulator, EmulatorSpi, NoDelay};
+    use crate::interface::eh1::SpiInterface;
+    use crate::protocol::{decode, Command, Token};
+    use crate::Geometry;
+
+    /// Passes transactions on to an emulator while keeping a copy of the
+    /// bytes written.
+    struct Recorder<'a> {
+        spi: EmulatorSpi<'a>,
+        sent: &'a RefCell<Vec<u8>>,
+    }
+
+    impl ErrorType for Recorder<'_> {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for Recorder<'_> {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+            for operation in operations.iter() {
+                if let Operation::Write(words) = operation {
+                    self.sent.borrow_mut().extend_from_slice(words);
+                }
+            }
+
+            self.spi.transaction(operations)
+        }
+    }
+
+    type TestBuffer<'a> = FrameBuffer<SpiInterface<Recorder<'a>>, NoDelay>;
+
+    fn framebuffer<'a>(
+        emulator: &'a RefCell<Emulator>,
+        sent: &'a RefCell<Vec<u8>>,
+    ) -> TestBuffer<'a> {
+        let geometry = emulator.borrow().geometry();
+        let spi = Recorder {
+            spi: EmulatorSpi::new(emulator),
+            sent,
+        };
+
+        FrameBuffer::new(SerLCD::new_spi_device(spi, NoDelay).with_geometry(geometry))
+    }
+
+    /// Flushes `fb`, returning what was sent.
+    fn flush(fb: &mut TestBuffer<'_>, sent: &RefCell<Vec<u8>>) -> Vec<u8> {
+        sent.borrow_mut().clear();
+        fb.flush().unwrap();
+        sent.borrow().clone()
+    }
+
+    #[test]
+    fn unchanged_buffer_sends_nothing() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        assert!(flush(&mut fb, &sent).is_empty());
+    }
+
+    #[test]
+    fn single_change_sends_one_cursor_move_and_one_byte() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        fb.draw_char(5, 1, b'x').unwrap();
+        let bytes = flush(&mut fb, &sent);
+
+        assert_eq!(
+            decode(&bytes).collect::<Vec<_>>(),
+            [
+                Token::Command(Command::SetCursor { address: 0x45 }),
+                Token::Text(b"x"),
+            ]
+        );
+        assert_eq!(emulator.borrow().char_at(5, 1), Some(b'x'));
+    }
+
+    #[test]
+    fn nearby_changes_are_merged_into_one_run() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        fb.draw_char(2, 0, b'a').unwrap();
+        fb.draw_char(6, 0, b'b').unwrap();
+        let bytes = flush(&mut fb, &sent);
+
+        assert_eq!(
+            decode(&bytes).collect::<Vec<_>>(),
+            [
+                Token::Command(Command::SetCursor { address: 0x02 }),
+                Token::Text(b"a   b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn distant_changes_are_sent_as_separate_runs() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        fb.draw_char(2, 0, b'a').unwrap();
+        fb.draw_char(7, 0, b'b').unwrap();
+        let bytes = flush(&mut fb, &sent);
+
+        assert_eq!(
+            decode(&bytes).collect::<Vec<_>>(),
+            [
+                Token::Command(Command::SetCursor { address: 0x02 }),
+                Token::Text(b"a"),
+                Token::Command(Command::SetCursor { address: 0x07 }),
+                Token::Text(b"b"),
+            ]
+        );
+        assert_eq!(&emulator.borrow().row(0)[..8], b"  a    b");
+    }
+
+    #[test]
+    fn invalidate_redraws_every_row() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd16x2));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        fb.draw_str(0, 0, "hello").unwrap();
+        flush(&mut fb, &sent);
+        fb.invalidate();
+        let bytes = flush(&mut fb, &sent);
+
+        assert_eq!(
+            decode(&bytes).collect::<Vec<_>>(),
+            [
+                Token::Command(Command::SetCursor { address: 0x00 }),
+                Token::Text(b"hello           "),
+                Token::Command(Command::SetCursor { address: 0x40 }),
+                Token::Text(b"                "),
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_are_cut_off_at_the_end_of_the_row() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd20x4));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        fb.draw_str(17, 0, "abcdef").unwrap();
+        flush(&mut fb, &sent);
+
+        let lcd = emulator.borrow();
+        assert_eq!(&lcd.row(0)[17..], b"abc");
+        assert_eq!(&*lcd.row(1), b"                    ");
+    }
+
+    #[test]
+    fn positions_off_the_panel_are_rejected() {
+        let emulator = RefCell::new(Emulator::new(Geometry::Lcd16x2));
+        let sent = RefCell::new(Vec::new());
+        let mut fb = framebuffer(&emulator, &sent);
+
+        assert!(matches!(
+            fb.draw_char(16, 0, b'x'),
+            Err(Error::OutOfBounds { col: 16, row: 0 })
+        ));
+        assert!(matches!(
+            fb.draw_str(0, 2, "x"),
+            Err(Error::OutOfBounds { col: 0, row: 2 })
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+//! Physical layouts of the SerLCD panels.
+
+use crate::protocol::Command;
+
+/// The character grid of the attached panel.
+///
+/// The SerLCD firmware supports 16 and 20 character wide panels with one, two
+/// or four lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Geometry {
+    Lcd16x1,
+    Lcd16x2,
+    Lcd16x4,
+    Lcd20x1,
+    Lcd20x2,
+    #[default]
+    Lcd20x4,
+}
+
+impl Geometry {
+    /// Layout with the given number of columns and rows, if it exists.
+    #[cfg(any(test, feature = "emulator"))]
+    pub(crate) fn from_size(columns: u8, rows: u8) -> Option<Self> {
+        let geometry = match (columns, rows) {
+            (16, 1) => Geometry::Lcd16x1,
+            (16, 2) => Geometry::Lcd16x2,
+            (16, 4) => Geometry::Lcd16x4,
+            (20, 1) => Geometry::Lcd20x1,
+            (20, 2) => Geometry::Lcd20x2,
+            (20, 4) => Geometry::Lcd20x4,
+            _ => return None,
+        };
+
+        Some(geometry)
+    }
+
+    pub fn columns(&self) -> u8 {
+        match self {
+            Geometry::Lcd16x1 | Geometry::Lcd16x2 | Geometry::Lcd16x4 => 16,
+            Geometry::Lcd20x1 | Geometry::Lcd20x2 | Geometry::Lcd20x4 => 20,
+        }
+    }
+
+    pub fn rows(&self) -> u8 {
+        match self {
+            Geometry::Lcd16x1 | Geometry::Lcd20x1 => 1,
+            Geometry::Lcd16x2 | Geometry::Lcd20x2 => 2,
+            Geometry::Lcd16x4 | Geometry::Lcd20x4 => 4,
+        }
+    }
+
+    /// DDRAM address of the character at `col`, `row`, or `None` if the
+    /// position is off the panel.
+    pub(crate) fn ddram_address(&self, col: u8, row: u8) -> Option<u8> {
+        if col < self.columns() && row < self.rows() {
+            Some(self.row_offsets()[row as usize] + col)
+        } else {
+            None
+        }
+    }
+
+    /// DDRAM address of the first character of each row.
+    pub(crate) fn row_offsets(&self) -> [u8; 4] {
+        match self.columns() {
+            16 => [0x00, 0x40, 0x10, 0x50],
+            _ => [0x00, 0x40, 0x14, 0x54],
+        }
+    }
+
+    /// Setting command telling the firmware the panel width.
+    pub(crate) fn width_command(&self) -> Command {
+        match self.columns() {
+            16 => Command::Width16,
+            _ => Command::Width20,
+        }
+    }
+
+    /// Setting command telling the firmware the number of lines.
+    pub(crate) fn lines_command(&self) -> Command {
+        match self.rows() {
+            1 => Command::Lines1,
+            2 => Command::Lines2,
+            _ => Command::Lines4,
+        }
+    }
+}
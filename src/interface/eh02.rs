@@ -0,0 +1,272 @@
+//! Transports for HALs implementing embedded-hal 0.2.
+
+use core::convert::Infallible;
+
+use embedded_hal_02::blocking::i2c;
+use embedded_hal_02::blocking::spi::{Transfer, Write};
+use embedded_hal_02::digital::v2::OutputPin;
+use embedded_hal_02::serial;
+
+use super::Interface;
+use crate::Error;
+
+/// SPI transport using a dedicated chip-select pin.
+pub struct SpiInterface<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+}
+
+impl<SPI, CS> SpiInterface<SPI, CS> {
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        Self { spi, cs }
+    }
+
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<SPI, CS, SpiE, PinE> Interface for SpiInterface<SPI, CS>
+where
+    SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    CS: OutputPin<Error = PinE>,
+    SpiE: core::fmt::Debug,
+    PinE: core::fmt::Debug,
+{
+    type BusError = SpiE;
+    type PinError = PinE;
+
+    fn begin_transmission(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        self.cs.set_low().map_err(Error::Pin)
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error<SpiE, PinE>> {
+        self.spi.write(data).map_err(Error::Bus)
+    }
+
+    fn end_transmission(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        self.cs.set_high().map_err(Error::Pin)
+    }
+}
+
+/// I2C transport addressing the display by its 7-bit address.
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cInterface<I2C> {
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Retargets the transport; does not reconfigure the display itself.
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, I2cE> Interface for I2cInterface<I2C>
+where
+    I2C: i2c::Write<Error = I2cE>,
+    I2cE: core::fmt::Debug,
+{
+    type BusError = I2cE;
+    type PinError = Infallible;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error<I2cE, Infallible>> {
+        self.i2c.write(self.address, data).map_err(Error::Bus)
+    }
+}
+
+/// UART transport writing to the display's RX pin.
+///
+/// The display listens at 9600 baud out of the box; configuring the port is
+/// left to the HAL.
+pub struct SerialInterface<TX> {
+    tx: TX,
+}
+
+impl<TX> SerialInterface<TX> {
+    pub fn new(tx: TX) -> Self {
+        Self { tx }
+    }
+
+    pub fn release(self) -> TX {
+        self.tx
+    }
+}
+
+impl<TX, SerialE> Interface for SerialInterface<TX>
+where
+    TX: serial::Write<u8, Error = SerialE>,
+    SerialE: core::fmt::Debug,
+{
+    type BusError = SerialE;
+    type PinError = Infallible;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error<SerialE, Infallible>> {
+        for b in data {
+            nb::block!(self.tx.write(*b)).map_err(Error::Bus)?;
+        }
+
+        Ok(())
+    }
+
+    fn end_transmission(&mut self) -> Result<(), Error<SerialE, Infallible>> {
+        nb::block!(self.tx.flush()).map_err(Error::Bus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::NoDelay;
+    use crate::SerLCD;
+
+    use super::*;
+
+    /// Records every write together with the address it went to.
+    #[derive(Default)]
+    struct RecordingI2c {
+        writes: Vec<(u8, Vec<u8>)>,
+    }
+
+    impl i2c::Write for RecordingI2c {
+        type Error = Infallible;
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+            self.writes.push((address, bytes.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn i2c_writes(
+        lcd: SerLCD<I2cInterface<RecordingI2c>, crate::LegacyDelay<NoDelay>>,
+    ) -> Vec<(u8, Vec<u8>)> {
+        lcd.release().0.release().writes
+    }
+
+    #[test]
+    fn i2c_frames_go_to_the_display_address() {
+        let mut lcd = SerLCD::new_i2c(RecordingI2c::default(), NoDelay);
+        lcd.clear().unwrap();
+        lcd.set_cursor(1, 1).unwrap();
+        lcd.write_str("hi").unwrap();
+
+        assert_eq!(
+            i2c_writes(lcd),
+            [
+                (0x72, vec![0x7c, 0x2d]),
+                (0x72, vec![0xfe, 0xc1]),
+                (0x72, b"hi".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn i2c_frames_go_to_a_custom_address() {
+        let mut lcd = SerLCD::new_i2c_with_address(RecordingI2c::default(), 0x30, NoDelay);
+        lcd.setup().unwrap();
+        lcd.home().unwrap();
+
+        let writes = i2c_writes(lcd);
+        assert_eq!(writes.len(), 2);
+        assert!(writes.iter().all(|(address, _)| *address == 0x30));
+    }
+
+    #[test]
+    fn change_address_moves_later_frames_to_the_new_address() {
+        let mut lcd = SerLCD::new_i2c(RecordingI2c::default(), NoDelay);
+
+        assert!(matches!(
+            lcd.change_address(0x07),
+            Err(crate::Error::InvalidAddress(0x07))
+        ));
+        assert!(matches!(
+            lcd.change_address(0x78),
+            Err(crate::Error::InvalidAddress(0x78))
+        ));
+
+        lcd.change_address(0x30).unwrap();
+        lcd.home().unwrap();
+
+        assert_eq!(
+            i2c_writes(lcd),
+            [(0x72, vec![0x7c, 0x19, 0x30]), (0x30, vec![0xfe, 0x02])]
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum SerialEvent {
+        Write(u8),
+        Flush,
+    }
+
+    /// Records bytes and flushes, refusing every first attempt so callers
+    /// have to retry.
+    #[derive(Default)]
+    struct RecordingSerial {
+        events: Vec<SerialEvent>,
+        busy: bool,
+        refused: usize,
+    }
+
+    impl RecordingSerial {
+        fn poll(&mut self) -> nb::Result<(), Infallible> {
+            self.busy = !self.busy;
+
+            if self.busy {
+                self.refused += 1;
+                Err(nb::Error::WouldBlock)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl serial::Write<u8> for RecordingSerial {
+        type Error = Infallible;
+
+        fn write(&mut self, word: u8) -> nb::Result<(), Infallible> {
+            self.poll()?;
+            self.events.push(SerialEvent::Write(word));
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Infallible> {
+            self.poll()?;
+            self.events.push(SerialEvent::Flush);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn serial_bytes_are_retried_and_flushed_per_transmission() {
+        let mut lcd = SerLCD::new_serial(RecordingSerial::default(), NoDelay);
+        lcd.clear().unwrap();
+        lcd.write_str("ab").unwrap();
+
+        let serial = lcd.release().0.release();
+
+        assert_eq!(
+            serial.events,
+            [
+                SerialEvent::Write(0x7c),
+                SerialEvent::Write(0x2d),
+                SerialEvent::Flush,
+                SerialEvent::Write(b'a'),
+                SerialEvent::Write(b'b'),
+                SerialEvent::Flush,
+            ]
+        );
+        assert_eq!(serial.refused, serial.events.len());
+    }
+}
@@ -0,0 +1,201 @@
+//! Transports for HALs implementing embedded-hal 1.0 and, with the `async`
+//! feature, embedded-hal-async.
+
+use core::convert::Infallible;
+
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::SpiDevice;
+
+#[cfg(feature = "async")]
+use super::AsyncInterface;
+use super::Interface;
+use crate::Error;
+
+/// SPI transport over a [`SpiDevice`], which manages chip select itself.
+pub struct SpiInterface<SPI> {
+    spi: SPI,
+}
+
+impl<SPI> SpiInterface<SPI> {
+    pub fn new(spi: SPI) -> Self {
+        Self { spi }
+    }
+
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI> Interface for SpiInterface<SPI>
+where
+    SPI: SpiDevice,
+{
+    type BusError = SPI::Error;
+    type PinError = Infallible;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error<SPI::Error, Infallible>> {
+        self.spi.write(data).map_err(Error::Bus)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<SPI> AsyncInterface for SpiInterface<SPI>
+where
+    SPI: embedded_hal_async::spi::SpiDevice,
+{
+    type BusError = SPI::Error;
+    type PinError = Infallible;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), Error<SPI::Error, Infallible>> {
+        self.spi.write(data).await.map_err(Error::Bus)
+    }
+}
+
+/// I2C transport addressing the display by its 7-bit address.
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cInterface<I2C> {
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Retargets the transport; does not reconfigure the display itself.
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C> Interface for I2cInterface<I2C>
+where
+    I2C: I2c,
+{
+    type BusError = I2C::Error;
+    type PinError = Infallible;
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Error<I2C::Error, Infallible>> {
+        self.i2c.write(self.address, data).map_err(Error::Bus)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C> AsyncInterface for I2cInterface<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c,
+{
+    type BusError = I2C::Error;
+    type PinError = Infallible;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), Error<I2C::Error, Infallible>> {
+        self.i2c.write(self.address, data).await.map_err(Error::Bus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write;
+
+    use embedded_hal::i2c::{ErrorType, Operation};
+    use embedded_hal::spi::{self, ErrorKind};
+
+    use crate::emulator::NoDelay;
+    use crate::{Error, SerLCD};
+
+    use super::*;
+
+    /// Records every write together with the address it went to.
+    #[derive(Default)]
+    struct RecordingI2c {
+        writes: Vec<(u8, Vec<u8>)>,
+    }
+
+    impl ErrorType for RecordingI2c {
+        type Error = Infallible;
+    }
+
+    impl I2c for RecordingI2c {
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Infallible> {
+            for operation in operations {
+                if let Operation::Write(bytes) = operation {
+                    self.writes.push((address, bytes.to_vec()));
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// SPI device whose every transaction fails.
+    struct FailingSpi;
+
+    impl spi::ErrorType for FailingSpi {
+        type Error = ErrorKind;
+    }
+
+    impl SpiDevice for FailingSpi {
+        fn transaction(
+            &mut self,
+            _operations: &mut [spi::Operation<'_, u8>],
+        ) -> Result<(), ErrorKind> {
+            Err(ErrorKind::Other)
+        }
+    }
+
+    #[test]
+    fn formatting_errors_keep_the_bus_error() {
+        let mut lcd = SerLCD::new_spi_device(FailingSpi, NoDelay);
+
+        assert_eq!(write!(lcd, "T={:.1}C", 21.5), Err(core::fmt::Error));
+        assert!(matches!(
+            lcd.take_error(),
+            Some(Error::Bus(ErrorKind::Other))
+        ));
+        assert!(lcd.take_error().is_none());
+    }
+
+    #[test]
+    fn change_address_rejects_reserved_addresses() {
+        let mut lcd = SerLCD::new_i2c_device(RecordingI2c::default(), NoDelay);
+
+        assert!(matches!(
+            lcd.change_address(0x07),
+            Err(Error::InvalidAddress(0x07))
+        ));
+        assert!(matches!(
+            lcd.change_address(0x78),
+            Err(Error::InvalidAddress(0x78))
+        ));
+
+        let (interface, _) = lcd.release();
+        assert_eq!(interface.address(), 0x72);
+        assert!(interface.release().writes.is_empty());
+    }
+
+    #[test]
+    fn change_address_moves_later_frames_to_the_new_address() {
+        let mut lcd = SerLCD::new_i2c_device(RecordingI2c::default(), NoDelay);
+        lcd.change_address(0x08).unwrap();
+        lcd.clear().unwrap();
+
+        let (interface, _) = lcd.release();
+        assert_eq!(interface.address(), 0x08);
+        assert_eq!(
+            interface.release().writes,
+            [(0x72, vec![0x7c, 0x19, 0x08]), (0x08, vec![0x7c, 0x2d])]
+        );
+    }
+}
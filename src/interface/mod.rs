@@ -0,0 +1,56 @@
+//! Bus transports the SerLCD can be driven over.
+
+use crate::Error;
+
+#[cfg(feature = "eh02")]
+pub mod eh02;
+pub mod eh1;
+
+/// A bus capable of carrying SerLCD command and text bytes.
+///
+/// The driver takes care of framing: every call to [`send`](Interface::send)
+/// carries bytes that are ready to go out on the wire as-is. Implement this
+/// trait to drive the display over buses not covered by this crate, such as
+/// bit-banged or bridged (USB-to-I2C) adapters.
+pub trait Interface {
+    /// Error raised by the data bus.
+    type BusError: core::fmt::Debug;
+    /// Error raised by any auxiliary pins, e.g. chip select.
+    type PinError: core::fmt::Debug;
+
+    /// Prepares the bus for a transmission, e.g. by asserting chip select.
+    fn begin_transmission(&mut self) -> Result<(), Error<Self::BusError, Self::PinError>> {
+        Ok(())
+    }
+
+    /// Sends a framed byte slice to the display.
+    fn send(&mut self, data: &[u8]) -> Result<(), Error<Self::BusError, Self::PinError>>;
+
+    /// Finishes a transmission, e.g. by releasing chip select.
+    fn end_transmission(&mut self) -> Result<(), Error<Self::BusError, Self::PinError>> {
+        Ok(())
+    }
+}
+
+/// Async counterpart of [`Interface`], used by [`AsyncSerLCD`](crate::AsyncSerLCD).
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncInterface {
+    /// Error raised by the data bus.
+    type BusError: core::fmt::Debug;
+    /// Error raised by any auxiliary pins, e.g. chip select.
+    type PinError: core::fmt::Debug;
+
+    /// Prepares the bus for a transmission, e.g. by asserting chip select.
+    async fn begin_transmission(&mut self) -> Result<(), Error<Self::BusError, Self::PinError>> {
+        Ok(())
+    }
+
+    /// Sends a framed byte slice to the display.
+    async fn send(&mut self, data: &[u8]) -> Result<(), Error<Self::BusError, Self::PinError>>;
+
+    /// Finishes a transmission, e.g. by releasing chip select.
+    async fn end_transmission(&mut self) -> Result<(), Error<Self::BusError, Self::PinError>> {
+        Ok(())
+    }
+}
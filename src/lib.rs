@@ -1,196 +1,718 @@
 //! This is an embedded-hal device driver for the Sparkfun SerLCD LCD screen.
-
-use embedded_hal as hal;
-use hal::blocking::delay::DelayMs;
-use hal::blocking::spi::{Transfer, Write};
-use hal::digital::v2::OutputPin;
+//!
+//! The crate is `no_std`; the `std` feature adds host-side extras such as a
+//! [`std::error::Error`] implementation for [`Error`].
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+use embedded_hal::delay::DelayNs;
+
+#[cfg(feature = "async")]
+mod asynch;
+mod baud;
+#[cfg(feature = "eh02")]
+mod delay;
+#[cfg(any(test, feature = "emulator"))]
+pub mod emulator;
+mod framebuffer;
+mod geometry;
+pub mod interface;
+pub mod protocol;
+mod state;
+mod timing;
+
+#[cfg(feature = "async")]
+pub use asynch::AsyncSerLCD;
+pub use baud::BaudRate;
+#[cfg(feature = "eh02")]
+pub use delay::LegacyDelay;
+pub use framebuffer::FrameBuffer;
+pub use geometry::Geometry;
+pub use interface::Interface;
+use protocol::Command;
+use state::{Frame, Repeat, State};
+pub use timing::Timing;
 
 #[derive(Debug)]
-pub enum Error<SpiE, PinE> {
-    Spi(SpiE),
+pub enum Error<BusE, PinE> {
+    Bus(BusE),
     Pin(PinE),
+    /// A custom character slot outside 0-7 was requested.
+    InvalidSlot(u8),
+    /// An I2C address outside the non-reserved 7-bit range was requested.
+    InvalidAddress(u8),
+    /// A cursor position off the configured geometry was requested.
+    OutOfBounds {
+        col: u8,
+        row: u8,
+    },
 }
 
-pub struct SerLCD<SPI, CS, DS> {
-    spi: SPI,
-    cs: CS,
-    delay_source: DS,
-    display_control: u8,
-    display_mode: u8,
+impl<BusE, PinE> core::fmt::Display for Error<BusE, PinE>
+where
+    BusE: core::fmt::Debug,
+    PinE: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Bus(e) => write!(f, "bus error: {:?}", e),
+            Error::Pin(e) => write!(f, "pin error: {:?}", e),
+            Error::InvalidSlot(slot) => write!(f, "invalid custom character slot {}", slot),
+            Error::InvalidAddress(address) => write!(f, "invalid I2C address {:#04x}", address),
+            Error::OutOfBounds { col, row } => {
+                write!(f, "cursor position ({}, {}) is off the display", col, row)
+            }
+        }
+    }
 }
 
-impl<SPI, CS, DS, SpiE, PinE> SerLCD<SPI, CS, DS>
+#[cfg(feature = "std")]
+impl<BusE, PinE> std::error::Error for Error<BusE, PinE>
 where
-    SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
-    CS: OutputPin<Error = PinE>,
-    DS: DelayMs<u8>,
-    SpiE: core::fmt::Debug,
+    BusE: core::fmt::Debug,
     PinE: core::fmt::Debug,
 {
-    pub fn new(spi: SPI, cs: CS, delay_source: DS) -> Self {
+}
+
+pub struct SerLCD<IFACE: Interface, DS> {
+    interface: IFACE,
+    delay_source: DS,
+    state: State,
+    timing: Timing,
+    /// Error stashed by the `core::fmt::Write` implementation.
+    last_error: Option<Error<IFACE::BusError, IFACE::PinError>>,
+}
+
+#[cfg(feature = "eh02")]
+mod eh02_constructors {
+    use core::convert::Infallible;
+
+    use embedded_hal_02::blocking::delay::DelayMs;
+    use embedded_hal_02::blocking::i2c;
+    use embedded_hal_02::blocking::spi::{Transfer, Write};
+    use embedded_hal_02::digital::v2::OutputPin;
+    use embedded_hal_02::serial;
+
+    use super::{Error, LegacyDelay, SerLCD, DISPLAY_ADDRESS};
+    use crate::interface::eh02::{I2cInterface, SerialInterface, SpiInterface};
+
+    impl<SPI, CS, DS, SpiE, PinE> SerLCD<SpiInterface<SPI, CS>, LegacyDelay<DS>>
+    where
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+        CS: OutputPin<Error = PinE>,
+        DS: DelayMs<u8>,
+        SpiE: core::fmt::Debug,
+        PinE: core::fmt::Debug,
+    {
+        pub fn new(spi: SPI, cs: CS, delay_source: DS) -> Self {
+            Self::with_interface(SpiInterface::new(spi, cs), LegacyDelay::new(delay_source))
+        }
+    }
+
+    impl<I2C, DS, I2cE> SerLCD<I2cInterface<I2C>, LegacyDelay<DS>>
+    where
+        I2C: i2c::Write<Error = I2cE>,
+        DS: DelayMs<u8>,
+        I2cE: core::fmt::Debug,
+    {
+        /// Creates a driver talking to the display at the default address (0x72).
+        pub fn new_i2c(i2c: I2C, delay_source: DS) -> Self {
+            Self::new_i2c_with_address(i2c, DISPLAY_ADDRESS, delay_source)
+        }
+
+        /// Creates a driver talking to the display at the given 7-bit address.
+        pub fn new_i2c_with_address(i2c: I2C, address: u8, delay_source: DS) -> Self {
+            Self::with_interface(
+                I2cInterface::new(i2c, address),
+                LegacyDelay::new(delay_source),
+            )
+        }
+
+        /// Moves the display to a new 7-bit I2C address.
+        ///
+        /// The command is sent to the current address, after which the driver
+        /// targets `address`. The display keeps the new address across resets.
+        pub fn change_address(&mut self, address: u8) -> Result<(), Error<I2cE, Infallible>> {
+            self.send_address_command(address)?;
+            self.interface.set_address(address);
+            Ok(())
+        }
+    }
+
+    impl<TX, DS, SerialE> SerLCD<SerialInterface<TX>, LegacyDelay<DS>>
+    where
+        TX: serial::Write<u8, Error = SerialE>,
+        DS: DelayMs<u8>,
+        SerialE: core::fmt::Debug,
+    {
+        /// Creates a driver writing to the display's RX pin over a UART.
+        pub fn new_serial(tx: TX, delay_source: DS) -> Self {
+            Self::with_interface(SerialInterface::new(tx), LegacyDelay::new(delay_source))
+        }
+    }
+}
+
+mod eh1_constructors {
+    use core::convert::Infallible;
+
+    use embedded_hal::delay::DelayNs;
+    use embedded_hal::i2c::I2c;
+    use embedded_hal::spi::SpiDevice;
+
+    use super::{Error, SerLCD, DISPLAY_ADDRESS};
+    use crate::interface::eh1::{I2cInterface, SpiInterface};
+
+    impl<SPI, DS> SerLCD<SpiInterface<SPI>, DS>
+    where
+        SPI: SpiDevice,
+        DS: DelayNs,
+    {
+        /// Creates a driver over an SPI device that manages its own chip select.
+        pub fn new_spi_device(spi: SPI, delay_source: DS) -> Self {
+            Self::with_interface(SpiInterface::new(spi), delay_source)
+        }
+    }
+
+    impl<I2C, DS> SerLCD<I2cInterface<I2C>, DS>
+    where
+        I2C: I2c,
+        DS: DelayNs,
+    {
+        /// Creates a driver talking to the display at the default address (0x72).
+        pub fn new_i2c_device(i2c: I2C, delay_source: DS) -> Self {
+            Self::new_i2c_device_with_address(i2c, DISPLAY_ADDRESS, delay_source)
+        }
+
+        /// Creates a driver talking to the display at the given 7-bit address.
+        pub fn new_i2c_device_with_address(i2c: I2C, address: u8, delay_source: DS) -> Self {
+            Self::with_interface(I2cInterface::new(i2c, address), delay_source)
+        }
+
+        /// Moves the display to a new 7-bit I2C address.
+        ///
+        /// The command is sent to the current address, after which the driver
+        /// targets `address`. The display keeps the new address across resets.
+        pub fn change_address(&mut self, address: u8) -> Result<(), Error<I2C::Error, Infallible>> {
+            self.send_address_command(address)?;
+            self.interface.set_address(address);
+            Ok(())
+        }
+    }
+}
+
+impl<IFACE, DS> SerLCD<IFACE, DS>
+where
+    IFACE: Interface,
+    DS: DelayNs,
+{
+    /// Creates a driver over any [`Interface`] implementation.
+    pub fn with_interface(interface: IFACE, delay_source: DS) -> Self {
         Self {
-            spi,
-            cs,
+            interface,
             delay_source,
-            display_control: LCD_DISPLAYON | LCD_CURSOROFF | LCD_BLINKOFF,
-            display_mode: LCD_ENTRYLEFT | LCD_ENTRYSHIFTDECREMENT,
+            state: State::new(),
+            timing: Timing::default(),
+            last_error: None,
         }
     }
 
-    pub fn setup(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.begin_transmission()?;
-        self.transmit(SPECIAL_COMMAND)?;
-        self.transmit(LCD_DISPLAYCONTROL)?;
-        self.transmit(SPECIAL_COMMAND)?;
-        self.transmit(LCD_ENTRYMODESET)?;
-        self.transmit(SETTING_COMMAND)?;
-        self.transmit(CLEAR_COMMAND)?;
-        self.end_transmission()?;
+    /// Sets the panel layout, which is pushed to the display by `setup`.
+    pub fn with_geometry(mut self, geometry: Geometry) -> Self {
+        self.state.geometry = geometry;
+        self
+    }
 
-        self.delay_source.delay_ms(50);
+    pub fn geometry(&self) -> Geometry {
+        self.state.geometry
+    }
 
-        Ok(())
+    /// Sets the delays waited out around bus traffic.
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
     }
 
-    pub fn command(&mut self, command: u8) -> Result<(), Error<SpiE, PinE>> {
-        self.begin_transmission()?;
-        self.transmit(SETTING_COMMAND)?;
-        self.transmit(command)?;
-        self.end_transmission()?;
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
 
-        self.delay_source.delay_ms(10);
+    /// Takes the bus error behind the last `fmt::Error` returned by `write!`.
+    pub fn take_error(&mut self) -> Option<Error<IFACE::BusError, IFACE::PinError>> {
+        self.last_error.take()
+    }
 
-        Ok(())
+    /// Releases the underlying bus and delay source.
+    pub fn release(self) -> (IFACE, DS) {
+        (self.interface, self.delay_source)
     }
 
-    pub fn special_command(&mut self, command: u8) -> Result<(), Error<SpiE, PinE>> {
+    /// Pushes the full cached state to the display and clears it.
+    ///
+    /// This covers display, cursor and blink flags, entry mode, geometry and,
+    /// once set, backlight and contrast.
+    pub fn setup(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let frame = self.state.init_frame();
+
         self.begin_transmission()?;
-        self.transmit(SPECIAL_COMMAND)?;
-        self.transmit(command)?;
+        self.transmit(frame.as_slice())?;
         self.end_transmission()?;
 
-        self.delay_source.delay_ms(50);
+        self.delay_source.delay_us(self.timing.special_command_us);
 
         Ok(())
     }
 
+    /// Recovers a display that lost power, e.g. after a brown-out.
+    ///
+    /// Waits for the display to finish booting, then runs [`setup`](Self::setup)
+    /// again so the hardware matches the state the driver believes in.
+    pub fn reinit(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.delay_source.delay_us(self.timing.boot_us);
+        self.setup()
+    }
+
+    pub fn command(&mut self, command: u8) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::Setting(command))
+    }
+
+    pub fn special_command(
+        &mut self,
+        command: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.special(&Command::Special(command))
+    }
+
+    /// Sends `command` `count` times, batched into as few bus transfers as
+    /// the display's receive buffer allows.
     pub fn special_command_count(
         &mut self,
         command: u8,
         count: u8,
-    ) -> Result<(), Error<SpiE, PinE>> {
-        self.begin_transmission()?;
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Special(command), count)
+    }
 
-        for _ in 0..count {
-            self.transmit(SPECIAL_COMMAND)?;
-            self.transmit(command)?;
-        }
+    pub fn clear(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::Clear)?;
+        self.delay_source.delay_us(self.timing.clear_us);
+        Ok(())
+    }
+
+    pub fn home(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.special(&Command::Home)
+    }
+
+    /// Moves the cursor to `col`, `row`, counting from zero.
+    ///
+    /// Positions off the configured geometry are rejected with
+    /// [`Error::OutOfBounds`] rather than spilling into another row.
+    pub fn set_cursor(
+        &mut self,
+        col: u8,
+        row: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let address = self
+            .state
+            .geometry
+            .ddram_address(col, row)
+            .ok_or(Error::OutOfBounds { col, row })?;
+
+        self.special(&Command::SetCursor { address })
+    }
 
+    /// Writes raw bytes at the cursor, batched into as few bus transfers as
+    /// the display's receive buffer allows.
+    pub fn write(&mut self, buf: &[u8]) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.begin_transmission()?;
+        self.transmit_chunks(buf.chunks(MAX_CHUNK_LEN))?;
         self.end_transmission()?;
 
-        self.delay_source.delay_ms(50);
+        self.delay_source.delay_us(self.timing.write_us);
 
         Ok(())
     }
 
-    pub fn clear(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.command(CLEAR_COMMAND)?;
-        self.delay_source.delay_ms(10);
+    pub fn write_str(&mut self, s: &str) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        if !s.is_empty() {
+            self.write(s.as_bytes())?;
+        }
+
         Ok(())
     }
 
-    pub fn home(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.special_command(LCD_RETURNHOME)
+    pub fn no_display(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control &= !LCD_DISPLAYON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+    }
+
+    pub fn display(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control |= LCD_DISPLAYON;
+        self.special(&Command::DisplayControl(self.state.display_control))
     }
 
-    pub fn set_cursor(&mut self, col: u8, row: u8) -> Result<(), Error<SpiE, PinE>> {
-        let row_offsets = [0x00, 0x40, 0x14, 0x54];
+    pub fn no_cursor(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control &= !LCD_CURSORON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+    }
 
-        let mut row = std::cmp::max(0, row);
-        row = std::cmp::min(row, MAX_ROWS - 1);
+    pub fn cursor(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control |= LCD_CURSORON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+    }
 
-        self.special_command(LCD_SETDDRAMADDR | (col + row_offsets[row as usize]))?;
+    /// Sets the backlight color in one go using the fast RGB command.
+    pub fn set_backlight(
+        &mut self,
+        r: u8,
+        g: u8,
+        b: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight = [Some(r), Some(g), Some(b)];
 
-        Ok(())
+        self.setting(&Command::Rgb(r, g, b))
     }
 
-    pub fn write(&mut self, buf: &[u8]) -> Result<(), Error<SpiE, PinE>> {
-        self.begin_transmission()?;
+    /// Stops the cursor block from blinking.
+    pub fn no_blink(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control &= !LCD_BLINKON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+    }
 
-        for b in buf {
-            self.transmit(*b)?;
-        }
+    /// Makes the cursor block blink.
+    pub fn blink(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_control |= LCD_BLINKON;
+        self.special(&Command::DisplayControl(self.state.display_control))
+    }
 
-        self.end_transmission()?;
+    /// Makes text flow left to right from the cursor.
+    pub fn left_to_right(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode |= LCD_ENTRYLEFT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+    }
 
-        self.delay_source.delay_ms(10);
+    /// Makes text flow right to left from the cursor.
+    pub fn right_to_left(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode &= !LCD_ENTRYLEFT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+    }
 
-        Ok(())
+    /// Shifts the display on every write so text appears to grow from
+    /// a fixed cursor position, e.g. for right-aligned numeric entry.
+    pub fn autoscroll(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode |= LCD_ENTRYSHIFTINCREMENT;
+        self.special(&Command::EntryMode(self.state.display_mode))
     }
 
-    pub fn write_str(&mut self, s: &str) -> Result<(), Error<SpiE, PinE>> {
-        if !s.is_empty() {
-            self.write(s.as_bytes())?;
-        }
+    /// Keeps the display fixed while the cursor advances.
+    pub fn no_autoscroll(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.display_mode &= !LCD_ENTRYSHIFTINCREMENT;
+        self.special(&Command::EntryMode(self.state.display_mode))
+    }
 
+    /// Scrolls the whole display left by `count` characters.
+    pub fn scroll_display_left(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_DISPLAYMOVE | LCD_MOVELEFT), count)
+    }
+
+    /// Scrolls the whole display right by `count` characters.
+    pub fn scroll_display_right(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_DISPLAYMOVE | LCD_MOVERIGHT), count)
+    }
+
+    /// Moves the cursor left by `count` characters.
+    pub fn move_cursor_left(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_CURSORMOVE | LCD_MOVELEFT), count)
+    }
+
+    /// Moves the cursor right by `count` characters.
+    pub fn move_cursor_right(
+        &mut self,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command_count(&Command::Shift(LCD_CURSORMOVE | LCD_MOVERIGHT), count)
+    }
+
+    /// Sets the display contrast; lower values give darker characters.
+    ///
+    /// The display stores the value in EEPROM, so this waits for the EEPROM
+    /// write delay in [`Timing`] before the next command.
+    pub fn set_contrast(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.contrast = Some(value);
+
+        self.eeprom_setting(&Command::Contrast(value))
+    }
+
+    /// Stores a custom 5x8 glyph in one of the eight CGRAM slots.
+    ///
+    /// Each byte of `bitmap` is one row, top first, using its low five bits.
+    pub fn create_char(
+        &mut self,
+        slot: u8,
+        bitmap: [u8; 8],
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        check_slot(slot)?;
+
+        self.eeprom_setting(&Command::CreateChar { slot, bitmap })
+    }
+
+    /// Prints the custom glyph stored in `slot` at the cursor.
+    pub fn write_char(&mut self, slot: u8) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        check_slot(slot)?;
+        self.setting(&Command::WriteChar(slot))
+    }
+
+    /// Shows the splash screen when the display boots.
+    pub fn enable_splash(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::EnableSplash)
+    }
+
+    /// Skips the splash screen when the display boots.
+    pub fn disable_splash(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::DisableSplash)
+    }
+
+    /// Saves the current screen contents as the boot splash screen.
+    ///
+    /// The contents are written to EEPROM, which takes a few hundred
+    /// milliseconds, so this waits for the write to finish.
+    pub fn save_splash(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::SaveSplash)?;
+        self.delay_source.delay_us(self.timing.splash_save_us);
         Ok(())
     }
 
-    pub fn no_display(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.display_control &= !LCD_DISPLAYON;
-        self.special_command(LCD_DISPLAYCONTROL | self.display_control)?;
+    /// Shows the firmware's confirmation messages, e.g. "Contrast: 10", after
+    /// setting changes.
+    pub fn enable_system_messages(
+        &mut self,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::EnableSystemMessages)
+    }
+
+    /// Suppresses the firmware's confirmation messages after setting changes.
+    pub fn disable_system_messages(
+        &mut self,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::DisableSystemMessages)
+    }
+
+    /// Switches the display's UART to a new baud rate.
+    ///
+    /// The display keeps the rate across resets. When driving it over UART,
+    /// reconfigure the host port to match before sending anything else.
+    pub fn set_baud_rate(
+        &mut self,
+        rate: BaudRate,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::BaudRate(rate))
+    }
+
+    /// Reboots the display firmware, waits for it to come back up and
+    /// pushes the cached state again.
+    pub fn reset(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.setting(&Command::Reset)?;
+        self.reinit()
+    }
+
+    /// Sets the primary (red) backlight brightness, scaled from 0-255.
+    pub fn set_primary_brightness(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight[0] = Some(value);
+        self.setting(&Command::PrimaryBrightness(brightness_step(value)))
+    }
+
+    /// Sets the green backlight brightness, scaled from 0-255.
+    pub fn set_green_brightness(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight[1] = Some(value);
+        self.setting(&Command::GreenBrightness(brightness_step(value)))
+    }
+
+    /// Sets the blue backlight brightness, scaled from 0-255.
+    pub fn set_blue_brightness(
+        &mut self,
+        value: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.state.backlight[2] = Some(value);
+        self.setting(&Command::BlueBrightness(brightness_step(value)))
+    }
+
+    fn send_address_command(
+        &mut self,
+        address: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        check_address(address)?;
+
+        self.eeprom_setting(&Command::Address(address))
+    }
+
+    /// Sends a SerLCD setting and waits for the display to apply it.
+    fn setting(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command(command)?;
+        self.delay_source.delay_us(self.timing.command_us);
         Ok(())
     }
 
-    pub fn display(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.display_control |= LCD_DISPLAYON;
-        self.special_command(LCD_DISPLAYCONTROL | self.display_control)?;
+    /// Sends a SerLCD setting the display stores in EEPROM and waits for the
+    /// write to finish.
+    fn eeprom_setting(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command(command)?;
+        self.delay_source.delay_us(self.timing.eeprom_write_us);
         Ok(())
     }
 
-    pub fn no_cursor(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.display_control &= !LCD_CURSORON;
-        self.special_command(LCD_DISPLAYCONTROL | self.display_control)?;
+    /// Sends an HD44780 instruction and waits for the display to apply it.
+    fn special(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.send_command(command)?;
+        self.delay_source.delay_us(self.timing.special_command_us);
         Ok(())
     }
 
-    pub fn cursor(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.display_control |= LCD_CURSORON;
-        self.special_command(LCD_DISPLAYCONTROL | self.display_control)?;
+    /// Repeats `command` within one transmission, split into chunks of whole
+    /// frames, then waits once for the display to process them all.
+    fn send_command_count(
+        &mut self,
+        command: &Command,
+        count: u8,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let repeat = Repeat::new(command, count);
+
+        self.begin_transmission()?;
+        self.transmit_chunks(repeat.chunks())?;
+        self.end_transmission()?;
+
+        self.delay_source.delay_us(self.timing.special_command_us);
+
         Ok(())
     }
 
-    fn begin_transmission(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.cs.set_low().map_err(Error::Pin)?;
+    /// Encodes `command` and sends it as a single transmission.
+    fn send_command(
+        &mut self,
+        command: &Command,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        let frame = Frame::single(command);
+
+        self.begin_transmission()?;
+        self.transmit(frame.as_slice())?;
+        self.end_transmission()
+    }
+
+    fn begin_transmission(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.interface.begin_transmission()?;
 
-        self.delay_source.delay_ms(10);
+        self.delay_source
+            .delay_us(self.timing.transmission_setup_us);
 
         Ok(())
     }
 
-    fn end_transmission(&mut self) -> Result<(), Error<SpiE, PinE>> {
-        self.cs.set_high().map_err(Error::Pin)?;
+    fn end_transmission(&mut self) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.interface.end_transmission()?;
 
-        self.delay_source.delay_ms(10);
+        self.delay_source
+            .delay_us(self.timing.transmission_teardown_us);
 
         Ok(())
     }
 
-    fn transmit(&mut self, data: u8) -> Result<(), Error<SpiE, PinE>> {
-        let rc = self.spi.write(&[data]);
-        rc.map_err(Error::Spi)?;
+    /// Sends each chunk as its own transfer, pausing between them so the
+    /// display can drain its receive buffer.
+    fn transmit_chunks<'a>(
+        &mut self,
+        chunks: impl Iterator<Item = &'a [u8]>,
+    ) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        for (i, chunk) in chunks.enumerate() {
+            if i > 0 {
+                self.delay_source.delay_us(self.timing.chunk_us);
+            }
+
+            self.transmit(chunk)?;
+        }
 
         Ok(())
     }
+
+    fn transmit(&mut self, data: &[u8]) -> Result<(), Error<IFACE::BusError, IFACE::PinError>> {
+        self.interface.send(data)
+    }
 }
 
-const DISPLAY_ADDRESS: u8 = 0x72;
-const MAX_ROWS: u8 = 4;
-const MAX_COLUMNS: u8 = 20;
+/// Allows formatting straight to the display with `write!`.
+///
+/// `fmt::Error` carries no detail, so the underlying error is kept and can be
+/// retrieved with [`SerLCD::take_error`].
+impl<IFACE, DS> core::fmt::Write for SerLCD<IFACE, DS>
+where
+    IFACE: Interface,
+    DS: DelayNs,
+{
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        SerLCD::write_str(self, s).map_err(|e| {
+            self.last_error = Some(e);
+            core::fmt::Error
+        })
+    }
+}
 
-const SPECIAL_COMMAND: u8 = 254;
-const SETTING_COMMAND: u8 = 0x7c;
+/// Scales a 0-255 brightness to one of the display's 30 backlight steps.
+fn brightness_step(value: u8) -> u8 {
+    (value as u16 * (BRIGHTNESS_STEPS - 1) as u16 / u8::MAX as u16) as u8
+}
 
+fn check_slot<BusE, PinE>(slot: u8) -> Result<(), Error<BusE, PinE>> {
+    if slot < CUSTOM_CHAR_SLOTS {
+        Ok(())
+    } else {
+        Err(Error::InvalidSlot(slot))
+    }
+}
+
+fn check_address<BusE, PinE>(address: u8) -> Result<(), Error<BusE, PinE>> {
+    if (MIN_ADDRESS..=MAX_ADDRESS).contains(&address) {
+        Ok(())
+    } else {
+        Err(Error::InvalidAddress(address))
+    }
+}
+
+const DISPLAY_ADDRESS: u8 = 0x72;
+/// Largest transfer the display reliably accepts at once, bounded by the
+/// 32 byte receive buffer of its I2C peripheral.
+const MAX_CHUNK_LEN: usize = 32;
+const MIN_ADDRESS: u8 = 0x08;
+const MAX_ADDRESS: u8 = 0x77;
+
+const RESET_COMMAND: u8 = 0x08;
 const CLEAR_COMMAND: u8 = 0x2d;
 const CONTRAST_COMMAND: u8 = 0x18;
 const ADDRESS_COMMAND: u8 = 0x19;
@@ -200,20 +722,34 @@ const DISABLE_SYSTEM_MESSAGE_DISPLAY: u8 = 0x2f;
 const ENABLE_SPLASH_DISPLAY: u8 = 0x30;
 const DISABLE_SPLASH_DISPLAY: u8 = 0x31;
 const SAVE_CURRENT_DISPLAY_AS_SPLASH: u8 = 0x0a;
+const WIDTH_20_COMMAND: u8 = 0x03;
+const WIDTH_16_COMMAND: u8 = 0x04;
+const LINES_4_COMMAND: u8 = 0x05;
+const LINES_2_COMMAND: u8 = 0x06;
+const LINES_1_COMMAND: u8 = 0x07;
+
+const CUSTOM_CHAR_SLOTS: u8 = 8;
+const CREATE_CHAR_BASE: u8 = 27;
+const WRITE_CHAR_BASE: u8 = 35;
+
+const BRIGHTNESS_STEPS: u8 = 30;
+const PRIMARY_BRIGHTNESS_BASE: u8 = 128;
+const GREEN_BRIGHTNESS_BASE: u8 = 158;
+const BLUE_BRIGHTNESS_BASE: u8 = 188;
 
 const LCD_RETURNHOME: u8 = 0x02;
 const LCD_ENTRYMODESET: u8 = 0x04;
 const LCD_DISPLAYCONTROL: u8 = 0x08;
 const LCD_CURSORSHIFT: u8 = 0x10;
+const LCD_FUNCTIONSET: u8 = 0x20;
+const LCD_SETCGRAMADDR: u8 = 0x40;
 const LCD_SETDDRAMADDR: u8 = 0x80;
 
-const LCD_ENTRYRIGHT: u8 = 0x00;
 const LCD_ENTRYLEFT: u8 = 0x02;
 const LCD_ENTRYSHIFTINCREMENT: u8 = 0x01;
 const LCD_ENTRYSHIFTDECREMENT: u8 = 0x00;
 
 const LCD_DISPLAYON: u8 = 0x04;
-const LCD_DISPLAYOFF: u8 = 0x00;
 const LCD_CURSORON: u8 = 0x02;
 const LCD_CURSOROFF: u8 = 0x00;
 const LCD_BLINKON: u8 = 0x01;
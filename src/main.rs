@@ -1,3 +1,110 @@
-pub fn main() {
-    println!("Test");
+//! Prints a readable transcript of a captured SerLCD byte stream.
+//!
+//! The capture is either raw binary or hex text as exported by most logic
+//! analyzers, e.g. `7C 2D 48 69` or `0x7c,0x2d,0x48,0x69`:
+//!
+//! ```text
+//! serlcd-decode capture.txt
+//! serlcd-decode --binary capture.bin
+//! ```
+//!
+//! Without a file the capture is read from standard input.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+
+use serlcd::protocol::{decode, Token};
+
+fn main() {
+    let mut binary = false;
+    let mut path = None;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--binary" => binary = true,
+            "-h" | "--help" => {
+                println!("usage: serlcd-decode [--binary] [FILE]");
+                return;
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => fail("too many arguments"),
+        }
+    }
+
+    let raw = match read_capture(path.as_deref()) {
+        Ok(raw) => raw,
+        Err(e) => fail(&e.to_string()),
+    };
+
+    let bytes = if binary {
+        raw
+    } else {
+        match parse_hex(&raw) {
+            Some(bytes) => bytes,
+            None => fail("capture is not hex text; pass --binary for raw captures"),
+        }
+    };
+
+    let mut tokens = decode(&bytes);
+
+    loop {
+        let offset = bytes.len() - tokens.as_slice().len();
+
+        match tokens.next() {
+            Some(Token::Text(text)) => {
+                println!("{:6}  text      \"{}\"", offset, text.escape_ascii())
+            }
+            Some(Token::Command(command)) => println!("{:6}  command   {:?}", offset, command),
+            Some(Token::Truncated(rest)) => println!("{:6}  truncated {:02x?}", offset, rest),
+            None => break,
+        }
+    }
+}
+
+fn read_capture(path: Option<&str>) -> io::Result<Vec<u8>> {
+    let mut raw = Vec::new();
+
+    match path {
+        Some(path) => File::open(path)?.read_to_end(&mut raw)?,
+        None => io::stdin().read_to_end(&mut raw)?,
+    };
+
+    Ok(raw)
+}
+
+/// Parses whitespace or comma separated hex bytes, with or without a `0x`
+/// prefix.
+fn parse_hex(raw: &[u8]) -> Option<Vec<u8>> {
+    let text = std::str::from_utf8(raw).ok()?;
+
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let digits = word
+                .strip_prefix("0x")
+                .or_else(|| word.strip_prefix("0X"))
+                .unwrap_or(word);
+            u8::from_str_radix(digits, 16).ok()
+        })
+        .collect()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("serlcd-decode: {}", message);
+    process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_captures_accept_prefixes_and_commas() {
+        assert_eq!(parse_hex(b"7C 2d\n48"), Some(vec![0x7c, 0x2d, 0x48]));
+        assert_eq!(parse_hex(b"0x7c,0X2D, 0x48,"), Some(vec![0x7c, 0x2d, 0x48]));
+        assert_eq!(parse_hex(b""), Some(vec![]));
+        assert_eq!(parse_hex(b"7c zz"), None);
+        assert_eq!(parse_hex(b"\xff\xfe"), None);
+    }
 }
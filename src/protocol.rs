@@ -0,0 +1,503 @@
+//! Sans-IO encoding of the SerLCD wire protocol.
+//!
+//! Text is sent to the display as raw bytes. Instructions for the HD44780
+//! controller are escaped with [`SPECIAL_COMMAND`], and settings of the
+//! SerLCD firmware with [`SETTING_COMMAND`]. This module only deals in bytes,
+//! so it can be tested on the host and reused by backends that do not go
+//! through embedded-hal.
+//!
+//! [`encode`] turns a [`Command`] into bytes. [`Decoder`] goes the other way
+//! one byte at a time, the way the display firmware reads its receive buffer,
+//! and [`decode`] splits a whole capture into commands and runs of text.
+
+use crate::*;
+
+/// Prefix of an HD44780 instruction.
+pub const SPECIAL_COMMAND: u8 = 0xfe;
+/// Prefix of a SerLCD firmware setting.
+pub const SETTING_COMMAND: u8 = 0x7c;
+
+/// Longest encoding of any [`Command`], in bytes.
+pub const MAX_ENCODED_LEN: usize = 10;
+
+/// A single instruction to the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Clears the screen through the firmware, which also homes the cursor.
+    Clear,
+    /// Returns the cursor to the top left corner.
+    Home,
+    /// Moves the cursor to a DDRAM address.
+    SetCursor {
+        address: u8,
+    },
+    /// Sets the display, cursor and blink flags.
+    DisplayControl(u8),
+    /// Sets the text direction and autoscroll flags.
+    EntryMode(u8),
+    /// Moves the cursor or shifts the display by one character.
+    Shift(u8),
+    Contrast(u8),
+    /// Moves the display to a new I2C address.
+    Address(u8),
+    /// Sets the backlight color with the fast RGB command.
+    Rgb(u8, u8, u8),
+    /// Sets the primary (red) backlight to one of 30 steps.
+    PrimaryBrightness(u8),
+    /// Sets the green backlight to one of 30 steps.
+    GreenBrightness(u8),
+    /// Sets the blue backlight to one of 30 steps.
+    BlueBrightness(u8),
+    /// Stores a 5x8 glyph in one of the eight CGRAM slots.
+    CreateChar {
+        slot: u8,
+        bitmap: [u8; 8],
+    },
+    /// Prints the glyph stored in a CGRAM slot.
+    WriteChar(u8),
+    Width16,
+    Width20,
+    Lines1,
+    Lines2,
+    Lines4,
+    EnableSystemMessages,
+    DisableSystemMessages,
+    EnableSplash,
+    DisableSplash,
+    SaveSplash,
+    BaudRate(BaudRate),
+    Reset,
+    /// Any other HD44780 instruction.
+    Special(u8),
+    /// Any other SerLCD setting.
+    Setting(u8),
+}
+
+/// Encodes `command` into the start of `buf`, returning the number of bytes
+/// written.
+///
+/// Arguments are masked to the bits the display understands, e.g. slots are
+/// taken modulo 8 and brightness steps are capped at 29.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than the encoding; [`MAX_ENCODED_LEN`] bytes
+/// always suffice.
+pub fn encode(command: &Command, buf: &mut [u8]) -> usize {
+    match *command {
+        Command::Clear => frame(buf, SETTING_COMMAND, &[CLEAR_COMMAND]),
+        Command::Home => frame(buf, SPECIAL_COMMAND, &[LCD_RETURNHOME]),
+        Command::SetCursor { address } => {
+            frame(buf, SPECIAL_COMMAND, &[LCD_SETDDRAMADDR | (address & 0x7f)])
+        }
+        Command::DisplayControl(flags) => {
+            frame(buf, SPECIAL_COMMAND, &[LCD_DISPLAYCONTROL | (flags & 0x07)])
+        }
+        Command::EntryMode(flags) => {
+            frame(buf, SPECIAL_COMMAND, &[LCD_ENTRYMODESET | (flags & 0x03)])
+        }
+        Command::Shift(flags) => frame(buf, SPECIAL_COMMAND, &[LCD_CURSORSHIFT | (flags & 0x0c)]),
+        Command::Contrast(value) => frame(buf, SETTING_COMMAND, &[CONTRAST_COMMAND, value]),
+        Command::Address(address) => frame(buf, SETTING_COMMAND, &[ADDRESS_COMMAND, address]),
+        Command::Rgb(r, g, b) => frame(buf, SETTING_COMMAND, &[SET_RGB_COMMAND, r, g, b]),
+        Command::PrimaryBrightness(step) => frame(
+            buf,
+            SETTING_COMMAND,
+            &[PRIMARY_BRIGHTNESS_BASE + max_step(step)],
+        ),
+        Command::GreenBrightness(step) => frame(
+            buf,
+            SETTING_COMMAND,
+            &[GREEN_BRIGHTNESS_BASE + max_step(step)],
+        ),
+        Command::BlueBrightness(step) => frame(
+            buf,
+            SETTING_COMMAND,
+            &[BLUE_BRIGHTNESS_BASE + max_step(step)],
+        ),
+        Command::CreateChar { slot, bitmap } => {
+            let mut body = [0; 9];
+            body[0] = CREATE_CHAR_BASE + slot % CUSTOM_CHAR_SLOTS;
+            body[1..].copy_from_slice(&bitmap);
+            frame(buf, SETTING_COMMAND, &body)
+        }
+        Command::WriteChar(slot) => frame(
+            buf,
+            SETTING_COMMAND,
+            &[WRITE_CHAR_BASE + slot % CUSTOM_CHAR_SLOTS],
+        ),
+        Command::Width16 => frame(buf, SETTING_COMMAND, &[WIDTH_16_COMMAND]),
+        Command::Width20 => frame(buf, SETTING_COMMAND, &[WIDTH_20_COMMAND]),
+        Command::Lines1 => frame(buf, SETTING_COMMAND, &[LINES_1_COMMAND]),
+        Command::Lines2 => frame(buf, SETTING_COMMAND, &[LINES_2_COMMAND]),
+        Command::Lines4 => frame(buf, SETTING_COMMAND, &[LINES_4_COMMAND]),
+        Command::EnableSystemMessages => {
+            frame(buf, SETTING_COMMAND, &[ENABLE_SYSTEM_MESSAGE_DISPLAY])
+        }
+        Command::DisableSystemMessages => {
+            frame(buf, SETTING_COMMAND, &[DISABLE_SYSTEM_MESSAGE_DISPLAY])
+        }
+        Command::EnableSplash => frame(buf, SETTING_COMMAND, &[ENABLE_SPLASH_DISPLAY]),
+        Command::DisableSplash => frame(buf, SETTING_COMMAND, &[DISABLE_SPLASH_DISPLAY]),
+        Command::SaveSplash => frame(buf, SETTING_COMMAND, &[SAVE_CURRENT_DISPLAY_AS_SPLASH]),
+        Command::BaudRate(rate) => frame(buf, SETTING_COMMAND, &[rate.command()]),
+        Command::Reset => frame(buf, SETTING_COMMAND, &[RESET_COMMAND]),
+        Command::Special(command) => frame(buf, SPECIAL_COMMAND, &[command]),
+        Command::Setting(command) => frame(buf, SETTING_COMMAND, &[command]),
+    }
+}
+
+fn frame(buf: &mut [u8], prefix: u8, body: &[u8]) -> usize {
+    buf[0] = prefix;
+    buf[1..=body.len()].copy_from_slice(body);
+    body.len() + 1
+}
+
+fn max_step(step: u8) -> u8 {
+    core::cmp::min(step, BRIGHTNESS_STEPS - 1)
+}
+
+/// Something the display received, as seen by a [`Decoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoded {
+    /// A character code to print at the cursor.
+    Char(u8),
+    Command(Command),
+}
+
+/// Incremental decoder for the byte stream the display receives.
+///
+/// Bytes are pushed one at a time as they arrive. Bytes of a command are
+/// buffered until the command is complete.
+#[derive(Debug, Default, Clone)]
+pub struct Decoder {
+    buf: [u8; MAX_ENCODED_LEN],
+    len: usize,
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; MAX_ENCODED_LEN],
+            len: 0,
+        }
+    }
+
+    /// Feeds the next byte, returning what it completes, if anything.
+    pub fn push(&mut self, byte: u8) -> Option<Decoded> {
+        if self.len == 0 && !is_prefix(byte) {
+            return Some(Decoded::Char(byte));
+        }
+
+        self.buf[self.len] = byte;
+        self.len += 1;
+
+        if self.len < encoded_len(&self.buf[..self.len]) {
+            return None;
+        }
+
+        let command = parse(&self.buf[..self.len]);
+        self.len = 0;
+
+        Some(Decoded::Command(command))
+    }
+
+    /// Bytes of a command that has not been completed yet.
+    pub fn pending(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Drops any partially received command.
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// A piece of a decoded byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Consecutive character codes printed at the cursor.
+    Text(&'a [u8]),
+    Command(Command),
+    /// A command cut off by the end of the stream.
+    Truncated(&'a [u8]),
+}
+
+/// Splits `bytes` into text runs and commands.
+pub fn decode(bytes: &[u8]) -> Decode<'_> {
+    Decode { rest: bytes }
+}
+
+/// Iterator returned by [`decode`].
+#[derive(Debug, Clone)]
+pub struct Decode<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Decode<'a> {
+    /// Bytes that have not been decoded yet.
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.rest
+    }
+}
+
+impl<'a> Iterator for Decode<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = self.rest;
+        let first = *rest.first()?;
+
+        if !is_prefix(first) {
+            let len = rest
+                .iter()
+                .position(|&b| is_prefix(b))
+                .unwrap_or(rest.len());
+            self.rest = &rest[len..];
+            return Some(Token::Text(&rest[..len]));
+        }
+
+        let mut decoder = Decoder::new();
+        for (i, &byte) in rest.iter().enumerate() {
+            if let Some(Decoded::Command(command)) = decoder.push(byte) {
+                self.rest = &rest[i + 1..];
+                return Some(Token::Command(command));
+            }
+        }
+
+        self.rest = &[];
+        Some(Token::Truncated(rest))
+    }
+}
+
+fn is_prefix(byte: u8) -> bool {
+    byte == SPECIAL_COMMAND || byte == SETTING_COMMAND
+}
+
+/// Full length of the command starting with `bytes`, or a lower bound while
+/// the command byte itself is still missing.
+fn encoded_len(bytes: &[u8]) -> usize {
+    match bytes {
+        [SETTING_COMMAND, code, ..] => 2 + setting_args(*code),
+        _ => 2,
+    }
+}
+
+fn setting_args(code: u8) -> usize {
+    match code {
+        CONTRAST_COMMAND | ADDRESS_COMMAND => 1,
+        SET_RGB_COMMAND => 3,
+        c if (CREATE_CHAR_BASE..WRITE_CHAR_BASE).contains(&c) => 8,
+        _ => 0,
+    }
+}
+
+fn parse(bytes: &[u8]) -> Command {
+    match *bytes {
+        [SPECIAL_COMMAND, instruction] => parse_special(instruction),
+        [SETTING_COMMAND, code, ref args @ ..] => parse_setting(code, args),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_special(instruction: u8) -> Command {
+    // Instructions are told apart by their highest set bit.
+    match instruction {
+        i if i & LCD_SETDDRAMADDR != 0 => Command::SetCursor { address: i & 0x7f },
+        i if i & (LCD_SETCGRAMADDR | LCD_FUNCTIONSET) != 0 => Command::Special(i),
+        i if i & LCD_CURSORSHIFT != 0 => Command::Shift(i & 0x0c),
+        i if i & LCD_DISPLAYCONTROL != 0 => Command::DisplayControl(i & 0x07),
+        i if i & LCD_ENTRYMODESET != 0 => Command::EntryMode(i & 0x03),
+        i if i & LCD_RETURNHOME != 0 => Command::Home,
+        i => Command::Special(i),
+    }
+}
+
+fn parse_setting(code: u8, args: &[u8]) -> Command {
+    let in_steps = |base: u8| code.wrapping_sub(base) < BRIGHTNESS_STEPS;
+
+    match code {
+        CLEAR_COMMAND => Command::Clear,
+        CONTRAST_COMMAND => Command::Contrast(args[0]),
+        ADDRESS_COMMAND => Command::Address(args[0]),
+        SET_RGB_COMMAND => Command::Rgb(args[0], args[1], args[2]),
+        c if in_steps(PRIMARY_BRIGHTNESS_BASE) => {
+            Command::PrimaryBrightness(c - PRIMARY_BRIGHTNESS_BASE)
+        }
+        c if in_steps(GREEN_BRIGHTNESS_BASE) => Command::GreenBrightness(c - GREEN_BRIGHTNESS_BASE),
+        c if in_steps(BLUE_BRIGHTNESS_BASE) => Command::BlueBrightness(c - BLUE_BRIGHTNESS_BASE),
+        c if (CREATE_CHAR_BASE..WRITE_CHAR_BASE).contains(&c) => {
+            let mut bitmap = [0; 8];
+            bitmap.copy_from_slice(args);
+            Command::CreateChar {
+                slot: c - CREATE_CHAR_BASE,
+                bitmap,
+            }
+        }
+        c if (WRITE_CHAR_BASE..WRITE_CHAR_BASE + CUSTOM_CHAR_SLOTS).contains(&c) => {
+            Command::WriteChar(c - WRITE_CHAR_BASE)
+        }
+        WIDTH_16_COMMAND => Command::Width16,
+        WIDTH_20_COMMAND => Command::Width20,
+        LINES_1_COMMAND => Command::Lines1,
+        LINES_2_COMMAND => Command::Lines2,
+        LINES_4_COMMAND => Command::Lines4,
+        ENABLE_SYSTEM_MESSAGE_DISPLAY => Command::EnableSystemMessages,
+        DISABLE_SYSTEM_MESSAGE_DISPLAY => Command::DisableSystemMessages,
+        ENABLE_SPLASH_DISPLAY => Command::EnableSplash,
+        DISABLE_SPLASH_DISPLAY => Command::DisableSplash,
+        SAVE_CURRENT_DISPLAY_AS_SPLASH => Command::SaveSplash,
+        RESET_COMMAND => Command::Reset,
+        c => match BaudRate::from_command(c) {
+            Some(rate) => Command::BaudRate(rate),
+            None => Command::Setting(c),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(command: Command) -> ([u8; MAX_ENCODED_LEN], usize) {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        let len = encode(&command, &mut buf);
+        (buf, len)
+    }
+
+    fn assert_encodes(command: Command, expected: &[u8]) {
+        let (buf, len) = encoded(command);
+        assert_eq!(&buf[..len], expected, "{:?}", command);
+    }
+
+    #[test]
+    fn hd44780_instructions_use_special_prefix() {
+        assert_encodes(Command::Home, &[0xfe, 0x02]);
+        assert_encodes(Command::SetCursor { address: 0x54 }, &[0xfe, 0xd4]);
+        assert_encodes(Command::DisplayControl(0x04), &[0xfe, 0x0c]);
+        assert_encodes(Command::EntryMode(0x02), &[0xfe, 0x06]);
+        assert_encodes(Command::Shift(0x08), &[0xfe, 0x18]);
+        assert_encodes(Command::Special(0x01), &[0xfe, 0x01]);
+    }
+
+    #[test]
+    fn settings_use_setting_prefix() {
+        assert_encodes(Command::Clear, &[0x7c, 0x2d]);
+        assert_encodes(Command::Contrast(40), &[0x7c, 0x18, 40]);
+        assert_encodes(Command::Address(0x30), &[0x7c, 0x19, 0x30]);
+        assert_encodes(Command::Rgb(1, 2, 3), &[0x7c, 0x2b, 1, 2, 3]);
+        assert_encodes(Command::Width16, &[0x7c, 0x04]);
+        assert_encodes(Command::Lines2, &[0x7c, 0x06]);
+        assert_encodes(Command::BaudRate(BaudRate::B115200), &[0x7c, 0x12]);
+        assert_encodes(Command::Reset, &[0x7c, 0x08]);
+    }
+
+    #[test]
+    fn brightness_steps_are_capped() {
+        assert_encodes(Command::PrimaryBrightness(0), &[0x7c, 128]);
+        assert_encodes(Command::GreenBrightness(29), &[0x7c, 187]);
+        assert_encodes(Command::BlueBrightness(200), &[0x7c, 217]);
+    }
+
+    #[test]
+    fn custom_characters_carry_slot_and_bitmap() {
+        let bitmap = [0x0e, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00, 0x00];
+        let (buf, len) = encoded(Command::CreateChar { slot: 2, bitmap });
+
+        assert_eq!(len, MAX_ENCODED_LEN);
+        assert_eq!(&buf[..2], &[0x7c, 29]);
+        assert_eq!(&buf[2..], &bitmap);
+
+        assert_encodes(Command::WriteChar(7), &[0x7c, 42]);
+    }
+
+    #[test]
+    fn arguments_are_masked() {
+        assert_encodes(Command::SetCursor { address: 0xff }, &[0xfe, 0xff]);
+        assert_encodes(Command::DisplayControl(0xff), &[0xfe, 0x0f]);
+        assert_encodes(Command::WriteChar(9), &[0x7c, 36]);
+    }
+
+    #[test]
+    fn decoding_inverts_encoding() {
+        let commands = [
+            Command::Clear,
+            Command::Home,
+            Command::SetCursor { address: 0x54 },
+            Command::DisplayControl(0x07),
+            Command::EntryMode(0x02),
+            Command::Shift(0x0c),
+            Command::Contrast(0x7c),
+            Command::Address(0x30),
+            Command::Rgb(0xfe, 0x7c, 0),
+            Command::PrimaryBrightness(29),
+            Command::GreenBrightness(0),
+            Command::BlueBrightness(15),
+            Command::CreateChar {
+                slot: 7,
+                bitmap: [0x7c, 0xfe, 1, 2, 3, 4, 5, 6],
+            },
+            Command::WriteChar(3),
+            Command::Width16,
+            Command::Lines1,
+            Command::EnableSystemMessages,
+            Command::SaveSplash,
+            Command::BaudRate(BaudRate::B1200),
+            Command::Reset,
+            Command::Special(0x01),
+            Command::Special(0x28),
+            Command::Special(0x38),
+            Command::Special(0x48),
+            Command::Setting(0x09),
+        ];
+
+        for command in commands.iter() {
+            let (buf, len) = encoded(*command);
+            let mut tokens = decode(&buf[..len]);
+
+            assert_eq!(tokens.next(), Some(Token::Command(*command)));
+            assert_eq!(tokens.next(), None);
+        }
+    }
+
+    #[test]
+    fn text_is_split_at_commands() {
+        let stream = b"Hi\xfe\xc0there\x7c\x2b\x01\x02\x03!";
+        let tokens: Vec<_> = decode(stream).collect();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Text(b"Hi"),
+                Token::Command(Command::SetCursor { address: 0x40 }),
+                Token::Text(b"there"),
+                Token::Command(Command::Rgb(1, 2, 3)),
+                Token::Text(b"!"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unfinished_command_is_truncated() {
+        let tokens: Vec<_> = decode(b"ok\x7c\x18").collect();
+        assert_eq!(
+            tokens,
+            [Token::Text(b"ok"), Token::Truncated(&[0x7c, 0x18])]
+        );
+    }
+
+    #[test]
+    fn decoder_buffers_until_complete() {
+        let mut decoder = Decoder::new();
+
+        assert_eq!(decoder.push(b'A'), Some(Decoded::Char(b'A')));
+        assert_eq!(decoder.push(0x7c), None);
+        assert_eq!(decoder.push(0x18), None);
+        assert_eq!(decoder.pending(), &[0x7c, 0x18]);
+        assert_eq!(
+            decoder.push(0x7c),
+            Some(Decoded::Command(Command::Contrast(0x7c)))
+        );
+        assert_eq!(decoder.push(b'B'), Some(Decoded::Char(b'B')));
+    }
+}
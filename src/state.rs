@@ -0,0 +1,128 @@
+//! Display state cached by the drivers so it can be pushed again after the
+//! display loses power.
+
+use crate::protocol::{self, Command};
+use crate::*;
+
+pub(crate) struct State {
+    pub geometry: Geometry,
+    pub display_control: u8,
+    pub display_mode: u8,
+    /// Last primary, green and blue backlight levels, if ever set.
+    pub backlight: [Option<u8>; 3],
+    pub contrast: Option<u8>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            geometry: Geometry::default(),
+            display_control: LCD_DISPLAYON | LCD_CURSOROFF | LCD_BLINKOFF,
+            display_mode: LCD_ENTRYLEFT | LCD_ENTRYSHIFTDECREMENT,
+            backlight: [None; 3],
+            contrast: None,
+        }
+    }
+
+    /// Builds the bytes that apply the whole cached state and clear the
+    /// screen.
+    ///
+    /// Backlight and contrast are only sent once the application has set
+    /// them, as the display otherwise keeps its own settings from EEPROM.
+    pub fn init_frame(&self) -> Frame {
+        let mut frame = Frame::new();
+
+        frame.push(&Command::DisplayControl(self.display_control));
+        frame.push(&Command::EntryMode(self.display_mode));
+        frame.push(&self.geometry.width_command());
+        frame.push(&self.geometry.lines_command());
+
+        if let [Some(r), Some(g), Some(b)] = self.backlight {
+            frame.push(&Command::Rgb(r, g, b));
+        } else {
+            let [r, g, b] = self.backlight;
+
+            if let Some(r) = r {
+                frame.push(&Command::PrimaryBrightness(brightness_step(r)));
+            }
+            if let Some(g) = g {
+                frame.push(&Command::GreenBrightness(brightness_step(g)));
+            }
+            if let Some(b) = b {
+                frame.push(&Command::BlueBrightness(brightness_step(b)));
+            }
+        }
+
+        if let Some(contrast) = self.contrast {
+            frame.push(&Command::Contrast(contrast));
+        }
+
+        frame.push(&Command::Clear);
+
+        frame
+    }
+}
+
+/// A small stack buffer for assembling multi-command transmissions.
+pub(crate) struct Frame {
+    buf: [u8; 24],
+    len: usize,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Self {
+            buf: [0; 24],
+            len: 0,
+        }
+    }
+
+    /// A frame holding just `command`.
+    pub fn single(command: &Command) -> Self {
+        let mut frame = Self::new();
+        frame.push(command);
+        frame
+    }
+
+    pub fn push(&mut self, command: &Command) {
+        self.len += protocol::encode(command, &mut self.buf[self.len..]);
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+/// One command repeated `count` times, split into chunks the display can
+/// take in one go.
+pub(crate) struct Repeat {
+    pattern: [u8; MAX_CHUNK_LEN],
+    pattern_len: usize,
+    total: usize,
+}
+
+impl Repeat {
+    pub fn new(command: &Command, count: u8) -> Self {
+        let single = Frame::single(command);
+        let len = single.as_slice().len();
+
+        // Only whole frames go into the pattern so every chunk starts on one.
+        let mut pattern = [0; MAX_CHUNK_LEN];
+        let pattern_len = MAX_CHUNK_LEN - MAX_CHUNK_LEN % len;
+        for frame in pattern[..pattern_len].chunks_exact_mut(len) {
+            frame.copy_from_slice(single.as_slice());
+        }
+
+        Self {
+            pattern,
+            pattern_len,
+            total: count as usize * len,
+        }
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.total)
+            .step_by(self.pattern_len)
+            .map(move |start| &self.pattern[..core::cmp::min(self.pattern_len, self.total - start)])
+    }
+}
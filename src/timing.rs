@@ -0,0 +1,100 @@
+//! Settle delays the drivers wait out around bus traffic.
+
+/// Per-operation delays, all in microseconds.
+///
+/// The default keeps the conservative delays this driver has always used,
+/// which work on any bus. The presets trade that margin for throughput on a
+/// specific transport. The EEPROM, splash and boot delays are bound by the
+/// firmware rather than the bus, so every preset shares them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// After starting a transmission, e.g. asserting chip select.
+    pub transmission_setup_us: u32,
+    /// After finishing a transmission, e.g. releasing chip select.
+    pub transmission_teardown_us: u32,
+    /// Between the chunks of a transmission too long for the display's
+    /// receive buffer.
+    pub chunk_us: u32,
+    /// After writing text.
+    pub write_us: u32,
+    /// After a SerLCD setting command.
+    pub command_us: u32,
+    /// After an HD44780 command, and after `setup`.
+    pub special_command_us: u32,
+    /// Extra time after the clear command on top of `command_us`.
+    pub clear_us: u32,
+    /// After a setting the display writes to EEPROM: contrast, custom
+    /// characters and the I2C address.
+    pub eeprom_write_us: u32,
+    /// After saving the splash screen, on top of `command_us`.
+    pub splash_save_us: u32,
+    /// Before `reinit` talks to a display that is still booting.
+    pub boot_us: u32,
+}
+
+const EEPROM_WRITE_US: u32 = 50_000;
+const SPLASH_SAVE_US: u32 = 300_000;
+const BOOT_US: u32 = 2_000_000;
+
+impl Timing {
+    /// Tuned for SPI, where the display buffers bytes as fast as they arrive.
+    pub const SPI: Timing = Timing {
+        transmission_setup_us: 10,
+        transmission_teardown_us: 10,
+        chunk_us: 2_000,
+        write_us: 500,
+        command_us: 5_000,
+        special_command_us: 2_000,
+        clear_us: 2_000,
+        eeprom_write_us: EEPROM_WRITE_US,
+        splash_save_us: SPLASH_SAVE_US,
+        boot_us: BOOT_US,
+    };
+
+    /// Tuned for I2C, where each chunk must be drained from the peripheral's
+    /// buffer before the next one arrives.
+    pub const I2C: Timing = Timing {
+        transmission_setup_us: 0,
+        transmission_teardown_us: 0,
+        chunk_us: 5_000,
+        write_us: 1_000,
+        command_us: 10_000,
+        special_command_us: 2_000,
+        clear_us: 2_000,
+        eeprom_write_us: EEPROM_WRITE_US,
+        splash_save_us: SPLASH_SAVE_US,
+        boot_us: BOOT_US,
+    };
+
+    /// Tuned for UART at 9600 baud, where the wire is slower than the
+    /// display processes bytes.
+    pub const UART: Timing = Timing {
+        transmission_setup_us: 0,
+        transmission_teardown_us: 0,
+        chunk_us: 0,
+        write_us: 500,
+        command_us: 5_000,
+        special_command_us: 2_000,
+        clear_us: 2_000,
+        eeprom_write_us: EEPROM_WRITE_US,
+        splash_save_us: SPLASH_SAVE_US,
+        boot_us: BOOT_US,
+    };
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing {
+            transmission_setup_us: 10_000,
+            transmission_teardown_us: 10_000,
+            chunk_us: 10_000,
+            write_us: 10_000,
+            command_us: 10_000,
+            special_command_us: 50_000,
+            clear_us: 10_000,
+            eeprom_write_us: EEPROM_WRITE_US,
+            splash_save_us: SPLASH_SAVE_US,
+            boot_us: BOOT_US,
+        }
+    }
+}